@@ -0,0 +1,133 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+pub const USAGE: &str = "\
+Usage: caoiscript <command> [options]
+
+Commands:
+  run <file> [--dump-context]   Typecheck and run a script
+  check <file>                  Tokenize, parse and typecheck a script without running it
+  tokens <file>                 Print the tokens produced by the tokenizer
+  ast <file>                    Print the tree produced by the parser
+  repl                          Start an interactive session
+  test [directory]              Run every test script under a directory (defaults to `tests`)
+  help                          Print this message
+
+Options:
+  --utf16-columns               Report columns in UTF-16 code units (for editors) when running or checking";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Run {
+        path: PathBuf,
+        dump_context: bool,
+        utf16_columns: bool,
+    },
+    Check {
+        path: PathBuf,
+        utf16_columns: bool,
+    },
+    Tokens {
+        path: PathBuf,
+    },
+    Ast {
+        path: PathBuf,
+    },
+    Repl,
+    Test {
+        directory: PathBuf,
+    },
+    Help,
+}
+
+#[derive(Error, Debug)]
+pub enum CommandError {
+    #[error("No command was provided")]
+    MissingCommand,
+
+    #[error("Unknown command `{0}`")]
+    UnknownCommand(String),
+
+    #[error("Unknown option `{0}`")]
+    UnknownOption(String),
+
+    // An option that exists, but belongs to a different command.
+    #[error("`{0}` can't be used with `{1}`")]
+    OptionNotSupported(String, String),
+
+    #[error("`{0}` expects a file path")]
+    MissingPath(String),
+
+    #[error("Unexpected argument `{0}`")]
+    UnexpectedArgument(String),
+}
+
+impl Command {
+    // Parses a command from the process arguments, excluding the program name.
+    pub fn parse(mut arguments: impl Iterator<Item = String>) -> Result<Self, CommandError> {
+        let Some(name) = arguments.next() else {
+            return Err(CommandError::MissingCommand);
+        };
+
+        let mut path = None;
+        let mut dump_context = false;
+        let mut utf16_columns = false;
+        let mut unsupported_option = None;
+
+        for argument in arguments {
+            match argument.as_str() {
+                "--dump-context" if name == "run" => dump_context = true,
+                "--utf16-columns" if name == "run" || name == "check" => utf16_columns = true,
+                "--dump-context" | "--utf16-columns" => {
+                    unsupported_option.get_or_insert(argument);
+                }
+                _ if argument.starts_with("--") => {
+                    return Err(CommandError::UnknownOption(argument))
+                }
+                _ if path.is_none() => path = Some(PathBuf::from(argument)),
+                _ => return Err(CommandError::UnexpectedArgument(argument)),
+            }
+        }
+
+        let require_path =
+            |path: Option<PathBuf>| path.ok_or(CommandError::MissingPath(name.clone()));
+
+        let command = match name.as_str() {
+            "run" => Command::Run {
+                path: require_path(path)?,
+                dump_context,
+                utf16_columns,
+            },
+            "check" => Command::Check {
+                path: require_path(path)?,
+                utf16_columns,
+            },
+            "tokens" => Command::Tokens {
+                path: require_path(path)?,
+            },
+            "ast" => Command::Ast {
+                path: require_path(path)?,
+            },
+            "repl" => match path {
+                Some(path) => {
+                    return Err(CommandError::UnexpectedArgument(path.display().to_string()))
+                }
+                None => Command::Repl,
+            },
+            "test" => Command::Test {
+                directory: path.unwrap_or_else(|| PathBuf::from("tests")),
+            },
+            "help" | "--help" | "-h" => Command::Help,
+
+            _ => return Err(CommandError::UnknownCommand(name)),
+        };
+
+        // Only reported once the command is known to exist, since the option isn't the problem otherwise.
+        if let Some(option) = unsupported_option {
+            return Err(CommandError::OptionNotSupported(option, name));
+        }
+
+        Ok(command)
+    }
+}
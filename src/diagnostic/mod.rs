@@ -0,0 +1,82 @@
+use std::env;
+use std::io::{self, IsTerminal};
+
+pub use renderer::*;
+
+use crate::location::Span;
+use crate::source_map::SourceMap;
+
+mod renderer;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+// A secondary span that adds context to a diagnostic, e.g. the type of one side of an operation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+// An error or warning reported by any phase, from the tokenizer to the interpreter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+
+    // The code that the diagnostic is about. Some diagnostics (e.g. unexpected end of file) have no span.
+    pub span: Option<Span>,
+
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            span: None,
+            labels: vec![],
+            notes: vec![],
+            help: None,
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    // Renders the diagnostic to stderr, using colour if stderr is a terminal and `NO_COLOR` isn't set.
+    pub fn emit(&self, sources: &SourceMap) {
+        let colour = io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none();
+        eprint!("{}", Renderer::new(colour).render(self, sources));
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::location::{FileId, Span};
+use crate::source_map::{ColumnUnit, SourceFile, SourceMap};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+
+// A span to underline, and the message to print after the underline.
+struct Annotation<'a> {
+    span: &'a Span,
+    message: Option<&'a str>,
+    primary: bool,
+}
+
+pub struct Renderer {
+    colour: bool,
+}
+
+impl Renderer {
+    pub fn new(colour: bool) -> Self {
+        Self { colour }
+    }
+
+    // Renders a diagnostic against the files it was reported for. Lines and columns are printed 1-based.
+    //
+    // error: Mismatched types: Integer and String
+    //  --> tests/int_plus_string.caoi:1:1
+    //   |
+    // 1 | 4 + "Hello"
+    //   | ^^^^^^^^^^^
+    //   | - Integer
+    //   |     ------- String
+    pub fn render(&self, diagnostic: &Diagnostic, sources: &SourceMap) -> String {
+        let mut output = String::new();
+
+        let (severity, severity_colour) = match diagnostic.severity {
+            Severity::Error => ("error", RED),
+            Severity::Warning => ("warning", YELLOW),
+        };
+
+        writeln!(
+            output,
+            "{}: {}",
+            self.paint(severity, severity_colour),
+            self.paint(&diagnostic.message, BOLD)
+        )
+        .unwrap();
+
+        let mut annotations = vec![];
+        if let Some(span) = &diagnostic.span {
+            annotations.push(Annotation {
+                span,
+                message: None,
+                primary: true,
+            });
+        }
+
+        for label in &diagnostic.labels {
+            annotations.push(Annotation {
+                span: &label.span,
+                message: Some(&label.message),
+                primary: false,
+            });
+        }
+
+        // Every file gets its own snippet, in the order that the files were first annotated.
+        let mut files: Vec<FileId> = vec![];
+        for annotation in &annotations {
+            if !files.contains(&annotation.span.file) {
+                files.push(annotation.span.file);
+            }
+        }
+
+        let width = annotations
+            .iter()
+            .map(|annotation| {
+                let file = sources.file(annotation.span.file);
+                (file.line_index(annotation.span.end) + 1).to_string().len()
+            })
+            .max()
+            .unwrap_or(0);
+
+        for (index, &file_id) in files.iter().enumerate() {
+            let file = sources.file(file_id);
+            let annotations: Vec<&Annotation> = annotations
+                .iter()
+                .filter(|annotation| annotation.span.file == file_id)
+                .collect();
+
+            // The first file contains the primary span (if there is one), which decides the position in the header.
+            let header_span = annotations[0].span;
+            let location = sources.start_location(header_span);
+            writeln!(
+                output,
+                "{}{} {}:{}:{}",
+                " ".repeat(width),
+                self.paint(if index == 0 { "-->" } else { ":::" }, BLUE),
+                file.name,
+                location.line + 1,
+                location.column + 1
+            )
+            .unwrap();
+
+            self.render_snippet(&mut output, file, &annotations, width, severity_colour);
+        }
+
+        for note in &diagnostic.notes {
+            writeln!(
+                output,
+                "{} {} {}",
+                " ".repeat(width),
+                self.paint("= note:", BOLD),
+                note
+            )
+            .unwrap();
+        }
+
+        if let Some(help) = &diagnostic.help {
+            writeln!(
+                output,
+                "{} {} {}",
+                " ".repeat(width),
+                self.paint("= help:", BOLD),
+                help
+            )
+            .unwrap();
+        }
+
+        // Leave a blank line between diagnostics.
+        writeln!(output).unwrap();
+        output
+    }
+
+    // Prints every line that is covered by an annotation, with the annotations underlined below it.
+    fn render_snippet(
+        &self,
+        output: &mut String,
+        file: &SourceFile,
+        annotations: &[&Annotation],
+        width: usize,
+        primary_colour: &str,
+    ) {
+        let gutter = self.paint(&format!("{} |", " ".repeat(width)), BLUE);
+        writeln!(output, "{}", gutter).unwrap();
+
+        let displayed: BTreeSet<usize> = annotations
+            .iter()
+            .flat_map(|annotation| {
+                file.line_index(annotation.span.start)..=file.line_index(annotation.span.end)
+            })
+            .collect();
+
+        let mut previous = None;
+        for &line in &displayed {
+            if previous.is_some_and(|previous| line > previous + 1) {
+                writeln!(output, "{}", self.paint("...", BLUE)).unwrap();
+            }
+
+            previous = Some(line);
+
+            let text = file.line(line);
+            let number = format!("{:>width$} |", line + 1, width = width);
+            writeln!(output, "{} {}", self.paint(&number, BLUE), text).unwrap();
+
+            for annotation in annotations {
+                // Underlines are positioned in characters, since that is what the terminal displays.
+                let start = file.location(annotation.span.start, ColumnUnit::Characters);
+                let end = file.location(annotation.span.end, ColumnUnit::Characters);
+                if line < start.line || line > end.line {
+                    continue;
+                }
+
+                let start_column = if line == start.line { start.column } else { 0 };
+                let end_column = if line == end.line {
+                    end.column
+                } else {
+                    text.chars().count()
+                };
+
+                // Empty spans (e.g. the end of the file) are still underlined with a single character.
+                let length = end_column.saturating_sub(start_column).max(1);
+
+                let (marker, colour) = if annotation.primary {
+                    ('^', primary_colour)
+                } else {
+                    ('-', BLUE)
+                };
+
+                let mut underline = marker.to_string().repeat(length);
+                if line == end.line {
+                    if let Some(message) = annotation.message {
+                        underline = format!("{} {}", underline, message);
+                    }
+                }
+
+                writeln!(
+                    output,
+                    "{} {}{}",
+                    gutter,
+                    " ".repeat(start_column),
+                    self.paint(&underline, colour)
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    fn paint(&self, text: &str, colour: &str) -> String {
+        if self.colour {
+            format!("{}{}{}", colour, text, RESET)
+        } else {
+            text.into()
+        }
+    }
+}
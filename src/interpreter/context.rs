@@ -1,20 +1,152 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
+use std::rc::{Rc, Weak};
 
 use super::value::Value;
+use crate::parser::FunctionNode;
 
+// The variables (including declared functions) defined by a block, a function call or the script itself.
 #[derive(Debug)]
-pub struct Context {
+pub struct Scope {
     name: String,
     variables: HashMap<String, Value>,
+
+    // The scope that this one is written inside of. For a function call, this is the scope that the function was
+    // created in rather than the caller's, so a function can only see the variables around its declaration.
+    parent: Option<Rc<RefCell<Scope>>>,
+}
+
+impl Scope {
+    fn new(name: &str, parent: Option<Rc<RefCell<Scope>>>) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            name: name.into(),
+            variables: HashMap::new(),
+            parent,
+        }))
+    }
+
+    fn define_variable(&mut self, name: &str, value: Value) {
+        let value = self.store(value);
+        self.variables.insert(name.into(), value);
+    }
+
+    fn set_variable(&mut self, name: &str, value: Value) -> bool {
+        if self.variables.contains_key(name) {
+            self.define_variable(name, value);
+            return true;
+        }
+
+        match &self.parent {
+            Some(parent) => parent.borrow_mut().set_variable(name, value),
+            None => false,
+        }
+    }
+
+    fn get_variable(&self, name: &str) -> Option<Value> {
+        match self.variables.get(name) {
+            Some(Value::Closure(closure)) => Some(Value::Closure(closure.upgrade())),
+            Some(value) => Some(value.clone()),
+            None => self.parent.as_ref()?.borrow().get_variable(name),
+        }
+    }
+
+    // A closure that is stored in the scope it captured only keeps a weak reference to it, since the scope would
+    // otherwise keep itself alive forever. It gets a strong reference back when it is read out of the scope.
+    fn store(&self, value: Value) -> Value {
+        match value {
+            Value::Closure(closure) if closure.captures(self) => {
+                Value::Closure(closure.downgrade())
+            }
+            value => value,
+        }
+    }
+}
+
+// A function as a value, along with the scope that it was created in, so that its body can see the variables around
+// it for as long as the function exists.
+#[derive(Clone)]
+pub struct Closure {
+    pub function: Rc<FunctionNode>,
+    environment: Environment,
+}
+
+#[derive(Clone)]
+enum Environment {
+    Strong(Rc<RefCell<Scope>>),
+    Weak(Weak<RefCell<Scope>>),
+}
+
+impl Closure {
+    // A new context for a call to the function, nested inside the scope that it was created in.
+    pub fn frame(&self) -> Context {
+        let parent = match &self.environment {
+            Environment::Strong(scope) => scope.clone(),
+            Environment::Weak(scope) => scope
+                .upgrade()
+                .expect("A closure is only weak while it is stored in the scope it captured"),
+        };
+
+        let name = self.function.name.as_deref().unwrap_or("Closure");
+        Context {
+            scope: Scope::new(name, Some(parent)),
+        }
+    }
+
+    fn captures(&self, scope: &Scope) -> bool {
+        match &self.environment {
+            Environment::Strong(environment) => std::ptr::eq(environment.as_ptr(), scope),
+            Environment::Weak(_) => false,
+        }
+    }
+
+    fn downgrade(self) -> Self {
+        let Environment::Strong(scope) = &self.environment else {
+            return self;
+        };
+
+        Self {
+            environment: Environment::Weak(Rc::downgrade(scope)),
+            ..self
+        }
+    }
+
+    fn upgrade(&self) -> Self {
+        let Environment::Weak(scope) = &self.environment else {
+            return self.clone();
+        };
+
+        let scope = scope
+            .upgrade()
+            .expect("A closure is only weak while it is stored in the scope it captured");
+
+        Self {
+            function: self.function.clone(),
+            environment: Environment::Strong(scope),
+        }
+    }
+}
+
+// The captured scope isn't printed, since it can contain the closure itself.
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Closure({:?})", self.function.name)
+    }
+}
+
+// The innermost scope that the interpreter is running in.
+#[derive(Debug)]
+pub struct Context {
+    scope: Rc<RefCell<Scope>>,
 }
 
 impl fmt::Display for Context {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "{}", self.name)?;
+        let scope = self.scope.borrow();
+        writeln!(f, "{}", scope.name)?;
 
-        for (name, value) in self.variables.clone() {
-            writeln!(f, "  - {} = {:?}", name, value)?;
+        for (name, value) in &scope.variables {
+            writeln!(f, "  - {} = {}", name, value)?;
         }
 
         Ok(())
@@ -24,16 +156,54 @@ impl fmt::Display for Context {
 impl Context {
     pub fn new(name: &str) -> Self {
         Self {
-            name: name.into(),
-            variables: HashMap::new(),
+            scope: Scope::new(name, None),
         }
     }
 
-    pub fn set_variable(&mut self, name: &str, value: Value) {
-        self.variables.insert(name.into(), value);
+    // Enters a new scope nested inside the current one. Variables that are defined before `pop` is called are only
+    // visible until then, and shadow any variables with the same name in the outer scopes.
+    pub fn push(&mut self, name: &str) {
+        self.scope = Scope::new(name, Some(self.scope.clone()));
     }
 
+    // Returns to the scope that the current one was nested in, forgetting any variables defined in it.
+    pub fn pop(&mut self) {
+        let parent = self.scope.borrow().parent.clone();
+        if let Some(parent) = parent {
+            self.scope = parent;
+        }
+    }
+
+    // Defines a variable in the current scope.
+    pub fn define_variable(&mut self, name: &str, value: Value) {
+        self.scope.borrow_mut().define_variable(name, value);
+    }
+
+    // Changes the value of a variable in the innermost scope that defines it. Returns false if no scope does.
+    pub fn set_variable(&mut self, name: &str, value: Value) -> bool {
+        self.scope.borrow_mut().set_variable(name, value)
+    }
+
+    // Finds a variable in the current scope, or in the scopes that it is nested in.
     pub fn get_variable(&self, name: &str) -> Option<Value> {
-        self.variables.get(name).cloned()
+        self.scope.borrow().get_variable(name)
+    }
+
+    // A copy of the variables defined in the current scope, which `restore` can put back.
+    pub fn snapshot(&self) -> HashMap<String, Value> {
+        self.scope.borrow().variables.clone()
+    }
+
+    // Puts back the variables from a snapshot of the current scope, forgetting anything defined since.
+    pub fn restore(&mut self, variables: HashMap<String, Value>) {
+        self.scope.borrow_mut().variables = variables;
+    }
+
+    // Creates a closure over the current scope, which a function declaration then stores as a variable.
+    pub fn capture(&self, function: FunctionNode) -> Closure {
+        Closure {
+            function: Rc::new(function),
+            environment: Environment::Strong(self.scope.clone()),
+        }
     }
 }
@@ -0,0 +1,115 @@
+use thiserror::Error;
+
+use super::MAX_CALL_DEPTH;
+use crate::diagnostic::Diagnostic;
+use crate::location::Span;
+
+// An arithmetic operation that has no result. The interpreter gives it a location to report it as an
+// `InterpreterError`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArithmeticError {
+    Overflow,
+    DivisionByZero,
+
+    // A string was repeated this many times.
+    NegativeRepetition(i64),
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum InterpreterError {
+    #[error("Integer overflow")]
+    Overflow(Span),
+
+    #[error("Division by zero")]
+    DivisionByZero { span: Span, divisor: Span },
+
+    #[error("Cannot repeat a string {count} times")]
+    NegativeRepetition { count: i64, span: Span },
+
+    // An index that is negative, or past the end of the list.
+    #[error("Index {index} is out of bounds for a list of length {length}")]
+    IndexOutOfBounds {
+        index: i64,
+        length: usize,
+        span: Span,
+        index_span: Span,
+    },
+
+    // A call that would nest more than `MAX_CALL_DEPTH` calls, usually because of endless recursion.
+    #[error("Stack overflow")]
+    StackOverflow(Span),
+
+    // The typechecker only lets a script use variables that it defines, but a function stored in the REPL can outlive
+    // the variables of an entry that was rolled back after a runtime error.
+    #[error("Undefined variable `{0}`")]
+    UndefinedVariable(String, Span),
+}
+
+impl InterpreterError {
+    pub fn span(&self) -> &Span {
+        match self {
+            InterpreterError::Overflow(span) => span,
+            InterpreterError::DivisionByZero { span, .. } => span,
+            InterpreterError::NegativeRepetition { span, .. } => span,
+            InterpreterError::IndexOutOfBounds { span, .. } => span,
+            InterpreterError::StackOverflow(span) => span,
+            InterpreterError::UndefinedVariable(_, span) => span,
+        }
+    }
+
+    // Locates an arithmetic error at the operation that caused it.
+    pub fn from_arithmetic(error: ArithmeticError, span: &Span, divisor: &Span) -> Self {
+        match error {
+            ArithmeticError::Overflow => InterpreterError::Overflow(span.clone()),
+            ArithmeticError::DivisionByZero => InterpreterError::DivisionByZero {
+                span: span.clone(),
+                divisor: divisor.clone(),
+            },
+            ArithmeticError::NegativeRepetition(count) => InterpreterError::NegativeRepetition {
+                count,
+                span: span.clone(),
+            },
+        }
+    }
+}
+
+impl From<&InterpreterError> for Diagnostic {
+    fn from(value: &InterpreterError) -> Self {
+        let diagnostic = Diagnostic::error(value.to_string()).with_span(value.span().clone());
+
+        match value {
+            InterpreterError::Overflow(_) => diagnostic.with_note(format!(
+                "Integers must be between {} and {}",
+                i64::MIN,
+                i64::MAX
+            )),
+
+            InterpreterError::DivisionByZero { divisor, .. } => {
+                diagnostic.with_label(divisor.clone(), "this is zero")
+            }
+
+            InterpreterError::NegativeRepetition { .. } => {
+                diagnostic.with_note("A string can only be repeated zero or more times")
+            }
+
+            InterpreterError::IndexOutOfBounds { index_span, .. } => diagnostic
+                .with_label(index_span.clone(), "this index")
+                .with_note("Lists are indexed from 0, so the last element is at the length minus one"),
+
+            InterpreterError::StackOverflow(_) => diagnostic.with_note(format!(
+                "Calls can only be nested {} deep, check for a recursive function that never returns",
+                MAX_CALL_DEPTH
+            )),
+
+            InterpreterError::UndefinedVariable(..) => diagnostic.with_note(
+                "The variable was forgotten because the REPL entry that defined it stopped with an error",
+            ),
+        }
+    }
+}
+
+impl<T> From<InterpreterError> for Result<T, InterpreterError> {
+    fn from(value: InterpreterError) -> Self {
+        Err(value)
+    }
+}
@@ -1,54 +1,164 @@
-mod context;
-mod value;
+pub mod context;
+pub mod error;
+pub mod value;
 
 use context::Context;
-use core::panic;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::io::{self, Write};
 use std::ops::Deref;
+use std::rc::Rc;
 
+use crate::location::Span;
+use crate::parser::{
+    BinaryOperationNode, BinaryOperator, CallNode, ForNode, IfNode, IndexAssignmentNode, IndexNode,
+    InterpolationPart, UnaryOperationNode, UnaryOperator, WhileNode,
+};
+use crate::typechecker::Type;
 use crate::{
-    parser::{AssignmentOperationNode, LetOperationNode, Literal, Node, VariableReference},
+    parser::{
+        AssignmentOperationNode, LetOperationNode, Literal, Node, TypeAnnotation, VariableReference,
+    },
     stream::ElementStream,
 };
-use crate::parser::{BinaryOperationNode, BinaryOperator};
 
+use self::error::InterpreterError;
 use self::value::Value;
 
+type Result<T> = std::result::Result<T, Interrupt>;
+
+// How many function calls can be nested inside each other before the script is stopped.
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+// Stops the evaluation of the current node, either because of a runtime error or because control flow is jumping
+// somewhere else (e.g. out of a loop with `break`).
+#[derive(Debug)]
+enum Interrupt {
+    Error(InterpreterError),
+    Break,
+    Continue,
+
+    // Leaves the current function with a value.
+    Return(Value),
+}
+
+impl From<InterpreterError> for Interrupt {
+    fn from(value: InterpreterError) -> Self {
+        Interrupt::Error(value)
+    }
+}
+
 pub struct Interpreter {
     stream: ElementStream<Node>,
+    output: Box<dyn Write>,
+
+    // How many function calls are currently running.
+    call_depth: usize,
 }
 
 impl Interpreter {
     pub fn new(nodes: Vec<Node>) -> Self {
+        Self::with_output(nodes, Box::new(io::stdout()))
+    }
+
+    // Creates an interpreter that writes anything printed by the script to `output` instead of stdout.
+    pub fn with_output(nodes: Vec<Node>, output: Box<dyn Write>) -> Self {
         Self {
             stream: ElementStream::new(nodes),
+            output,
+            call_depth: 0,
         }
     }
 
-    pub fn run(&mut self) {
+    // Runs the script in a new context. If a runtime error stops the script, the context is returned along with the
+    // error, so that the variables that were set before the error can still be inspected.
+    pub fn run(&mut self) -> (Context, Option<InterpreterError>) {
         let mut context = Context::new("Root");
+        let error = self.run_in(&mut context).err();
 
-        loop {
-            let Some(node) = self.stream.consume() else {
-                break;
-            };
+        (context, error)
+    }
 
-            self.interpret_node(&node, &mut context);
+    // Interprets the remaining nodes against an existing context, returning the value of the last node.
+    // Stops at the first runtime error.
+    pub fn run_in(
+        &mut self,
+        context: &mut Context,
+    ) -> std::result::Result<Value, InterpreterError> {
+        let mut value = Value::None;
+
+        while let Some(node) = self.stream.consume() {
+            value = match self.interpret_node(&node, context) {
+                Ok(value) => value,
+                Err(Interrupt::Error(error)) => return Err(error),
+                Err(interrupt) => unreachable!("{:?} outside of a loop or function", interrupt),
+            };
         }
 
-        println!("{}", context);
+        Ok(value)
     }
 
-    fn interpret_node(&mut self, node: &Node, context: &mut Context) -> Value {
+    fn interpret_node(&mut self, node: &Node, context: &mut Context) -> Result<Value> {
         match node {
-            Node::Literal(value, _) => self.interpret_literal(value),
+            Node::Literal(value, _) => Ok(self.interpret_literal(value)),
             Node::LetOperation(operation, _) => self.interpret_let_operation(operation, context),
-            Node::BinaryOperation(operation, _) => self.interpret_binary_operation(operation, context),
-            Node::AssignmentOperation(operation, _) => {
-                self.interpret_assignment_operation(operation, context)
+            Node::BinaryOperation(operation, span) => {
+                self.interpret_binary_operation(operation, span, context)
             }
-            Node::Reference(identifier, _) => context.get_variable(identifier).unwrap(),
+            Node::UnaryOperation(operation, span) => {
+                self.interpret_unary_operation(operation, span, context)
+            }
+            Node::Group(expression, _) => self.interpret_node(expression, context),
+            Node::AssignmentOperation(operation, span) => {
+                self.interpret_assignment_operation(operation, span, context)
+            }
+            Node::IndexAssignment(operation, span) => {
+                self.interpret_index_assignment(operation, span, context)
+            }
+            Node::Reference(identifier, span) => match context.get_variable(identifier) {
+                Some(value) => Ok(value),
+                None => Err(
+                    InterpreterError::UndefinedVariable(identifier.clone(), span.clone()).into(),
+                ),
+            },
+            Node::Print(expression, _) => self.interpret_print(expression, context),
+            Node::Interpolation(parts, _) => self.interpret_interpolation(parts, context),
+            Node::Block(nodes, _) => self.interpret_block(nodes, context),
+            Node::If(operation, _) => self.interpret_if(operation, context),
+            Node::While(operation, _) => self.interpret_while(operation, context),
+            Node::For(operation, _) => self.interpret_for(operation, context),
+            Node::Function(function, _) => {
+                let closure = context.capture(function.clone());
 
-            _ => panic!("Unable to interpret node: {:#?}", node),
+                // A declaration stores the function, while an anonymous function is used as a value.
+                match &function.name {
+                    Some(name) => {
+                        context.define_variable(name, Value::Closure(closure));
+                        Ok(Value::None)
+                    }
+                    None => Ok(Value::Closure(closure)),
+                }
+            }
+            Node::Call(call, span) => self.interpret_call(call, span, context),
+            Node::List(elements, _) => {
+                let mut values = vec![];
+                for element in elements {
+                    values.push(self.interpret_node(element, context)?);
+                }
+
+                Ok(Value::List(Rc::new(RefCell::new(values))))
+            }
+            Node::Index(operation, span) => self.interpret_index(operation, span, context),
+            Node::Return(expression, _) => {
+                let value = match expression {
+                    Some(expression) => self.interpret_node(expression, context)?,
+                    None => Value::None,
+                };
+
+                Err(Interrupt::Return(value))
+            }
+            Node::Break(_) => Err(Interrupt::Break),
+            Node::Continue(_) => Err(Interrupt::Continue),
         }
     }
 
@@ -56,51 +166,331 @@ impl Interpreter {
         match literal {
             Literal::String(value) => Value::String(value.clone()),
             Literal::Integer(value) => Value::Integer(*value),
+            Literal::Float(value) => Value::Float(*value),
+            Literal::Boolean(value) => Value::Boolean(*value),
+        }
+    }
+
+    fn interpret_print(&mut self, expression: &Node, context: &mut Context) -> Result<Value> {
+        let value = self.interpret_node(expression, context)?;
+        writeln!(self.output, "{}", value.to_print_string()).expect("Unable to write output");
+
+        Ok(Value::None)
+    }
+
+    fn interpret_interpolation(
+        &mut self,
+        parts: &[InterpolationPart],
+        context: &mut Context,
+    ) -> Result<Value> {
+        let mut string = String::new();
+
+        for part in parts {
+            match part {
+                InterpolationPart::Text(text) => string.push_str(text),
+                InterpolationPart::Expression(expression) => {
+                    let value = self.interpret_node(expression, context)?;
+                    string.push_str(&value.to_print_string());
+                }
+            }
+        }
+
+        Ok(Value::String(string))
+    }
+
+    // Runs the statements in a nested context, returning the value of the last one.
+    fn interpret_block(&mut self, nodes: &[Node], context: &mut Context) -> Result<Value> {
+        context.push("Block");
+
+        let mut result = Ok(Value::None);
+        for node in nodes {
+            result = self.interpret_node(node, context);
+            if result.is_err() {
+                break;
+            }
+        }
+
+        context.pop();
+        result
+    }
+
+    fn interpret_if(&mut self, operation: &IfNode, context: &mut Context) -> Result<Value> {
+        let condition = self.interpret_node(operation.condition.deref(), context)?;
+
+        if condition.to_boolean() {
+            return self.interpret_node(operation.then_branch.deref(), context);
+        }
+
+        match &operation.else_branch {
+            Some(else_branch) => self.interpret_node(else_branch.deref(), context),
+            None => Ok(Value::None),
+        }
+    }
+
+    // The body is a block, so each iteration gets a new context.
+    fn interpret_while(&mut self, operation: &WhileNode, context: &mut Context) -> Result<Value> {
+        while self
+            .interpret_node(operation.condition.deref(), context)?
+            .to_boolean()
+        {
+            match self.interpret_node(operation.body.deref(), context) {
+                Ok(_) | Err(Interrupt::Continue) => {}
+                Err(Interrupt::Break) => break,
+                Err(interrupt) => return Err(interrupt),
+            }
+        }
+
+        Ok(Value::None)
+    }
+
+    // Each iteration defines the loop variable in a new context, so the body (which is a block) can't change the
+    // values that are left to visit.
+    fn interpret_for(&mut self, operation: &ForNode, context: &mut Context) -> Result<Value> {
+        let iterable = self.interpret_node(operation.iterable.deref(), context)?;
+
+        for element in iterable.elements() {
+            context.push("For");
+            context.define_variable(&operation.variable, element);
+            let result = self.interpret_node(operation.body.deref(), context);
+            context.pop();
+
+            match result {
+                Ok(_) | Err(Interrupt::Continue) => {}
+                Err(Interrupt::Break) => break,
+                Err(interrupt) => return Err(interrupt),
+            }
         }
+
+        Ok(Value::None)
     }
 
     fn interpret_let_operation(
         &mut self,
         operation: &LetOperationNode,
         context: &mut Context,
-    ) -> Value {
-        let value = self.interpret_node(operation.expression.deref(), context);
+    ) -> Result<Value> {
+        let value = self.interpret_node(operation.expression.deref(), context)?;
+        let value = Self::widen(value, operation.type_identifier.as_ref());
+
+        context.define_variable(&operation.name_identifier, value.clone());
+        Ok(value)
+    }
+
+    // Each call runs in its own stack frame, which is nested inside the scope that the function was created in and
+    // starts with just the parameters. The callee and then the arguments are evaluated in the caller's context first.
+    fn interpret_call(
+        &mut self,
+        call: &CallNode,
+        span: &Span,
+        context: &mut Context,
+    ) -> Result<Value> {
+        let closure = match self.interpret_node(call.callee.deref(), context)? {
+            Value::Closure(closure) => closure,
+            value => panic!("Unable to call {:?}", value),
+        };
+
+        let function = closure.function.clone();
+
+        let mut arguments = vec![];
+        for (argument, parameter) in call.arguments.iter().zip(&function.parameters) {
+            let value = self.interpret_node(argument, context)?;
+            arguments.push(Self::widen(value, Some(&parameter.type_identifier)));
+        }
+
+        if self.call_depth == MAX_CALL_DEPTH {
+            return Err(InterpreterError::StackOverflow(span.clone()).into());
+        }
+
+        let mut frame = closure.frame();
+        for (parameter, value) in function.parameters.iter().zip(arguments) {
+            frame.define_variable(&parameter.name, value);
+        }
+
+        self.call_depth += 1;
+        let result = self.interpret_node(function.body.deref(), &mut frame);
+        self.call_depth -= 1;
+
+        let value = match result {
+            Ok(value) | Err(Interrupt::Return(value)) => value,
+            Err(interrupt) => return Err(interrupt),
+        };
 
-        context.set_variable(&operation.name_identifier, value.clone());
-        value
+        // A function without a return type doesn't give back the value of its body.
+        match &function.return_type {
+            Some(return_type) => Ok(Self::widen(value, Some(return_type))),
+            None => Ok(Value::None),
+        }
+    }
+
+    // The typechecker allows an Integer where a Float was declared (e.g. `let x: Float = 1`), so it has to be widened
+    // when it is stored.
+    fn widen(value: Value, type_identifier: Option<&TypeAnnotation>) -> Value {
+        match type_identifier {
+            Some(TypeAnnotation::Named(name, _))
+                if Type::from_string(name) == Some(Type::Float) =>
+            {
+                Value::Float(value.to_float())
+            }
+            _ => value,
+        }
     }
 
     fn interpret_assignment_operation(
         &mut self,
         operation: &AssignmentOperationNode,
+        span: &Span,
+        context: &mut Context,
+    ) -> Result<Value> {
+        let identifier = operation.identifier.identifier();
+        let value = self.interpret_node(operation.expression.deref(), context)?;
+
+        // The typechecker fills in the variable's type, and an Integer assigned to a Float variable has to be widened.
+        let value = match &operation.identifier {
+            VariableReference::Typed(_, Type::Float) => Value::Float(value.to_float()),
+            _ => value,
+        };
+
+        if !context.set_variable(identifier, value.clone()) {
+            return Err(
+                InterpreterError::UndefinedVariable(identifier.into(), span.clone()).into(),
+            );
+        }
+
+        Ok(value)
+    }
+
+    fn interpret_index(
+        &mut self,
+        operation: &IndexNode,
+        span: &Span,
+        context: &mut Context,
+    ) -> Result<Value> {
+        let list = self
+            .interpret_node(operation.target.deref(), context)?
+            .to_list();
+        let index = self
+            .interpret_node(operation.index.deref(), context)?
+            .to_integer();
+
+        let list = list.borrow();
+        let position = Self::element_position(&list, index, span, operation.index.span())?;
+        Ok(list[position].clone())
+    }
+
+    // The list is evaluated first, then the index, and then the value.
+    fn interpret_index_assignment(
+        &mut self,
+        operation: &IndexAssignmentNode,
+        span: &Span,
         context: &mut Context,
-    ) -> Value {
-        let identifier = match operation.identifier.clone() {
-            VariableReference::Unresolved(identifier) => identifier,
-            VariableReference::Typed(identifier, _) => identifier,
+    ) -> Result<Value> {
+        let list = self
+            .interpret_node(operation.target.deref(), context)?
+            .to_list();
+        let index = self
+            .interpret_node(operation.index.deref(), context)?
+            .to_integer();
+        let value = self.interpret_node(operation.expression.deref(), context)?;
+
+        // The typechecker fills in the element type, and an Integer stored in a list of floats has to be widened.
+        let value = match &operation.element_type {
+            Some(Type::Float) => Value::Float(value.to_float()),
+            _ => value,
         };
 
-        if let None = context.get_variable(&identifier) {
-            panic!("Unknown variable: {}", identifier);
+        let mut list = list.borrow_mut();
+        let position = Self::element_position(&list, index, span, operation.index.span())?;
+        list[position] = value.clone();
+
+        Ok(value)
+    }
+
+    // The position of the element at `index`, which is only valid if it is between 0 and the end of the list.
+    fn element_position(
+        list: &[Value],
+        index: i64,
+        span: &Span,
+        index_span: &Span,
+    ) -> Result<usize> {
+        match usize::try_from(index) {
+            Ok(position) if position < list.len() => Ok(position),
+            _ => Err(InterpreterError::IndexOutOfBounds {
+                index,
+                length: list.len(),
+                span: span.clone(),
+                index_span: index_span.clone(),
+            }
+            .into()),
         }
+    }
 
-        let value = self.interpret_node(operation.expression.deref(), context);
-        context.set_variable(&identifier, value.clone());
+    fn interpret_unary_operation(
+        &mut self,
+        operation: &UnaryOperationNode,
+        span: &Span,
+        context: &mut Context,
+    ) -> Result<Value> {
+        let value = self.interpret_node(operation.operand.deref(), context)?;
 
-        value
+        match operation.operator {
+            UnaryOperator::Negate => value
+                .negate()
+                .map_err(|error| InterpreterError::from_arithmetic(error, span, span).into()),
+            UnaryOperator::Plus => Ok(value),
+            UnaryOperator::Not => Ok(value.not()),
+        }
     }
 
     fn interpret_binary_operation(
         &mut self,
         operation: &BinaryOperationNode,
+        span: &Span,
         context: &mut Context,
-    ) -> Value {
-        let left_value = self.interpret_node(operation.left.deref(), context);
-        let right_value = self.interpret_node(operation.right.deref(), context);
+    ) -> Result<Value> {
+        let left_value = self.interpret_node(operation.left.deref(), context)?;
 
+        // `and` and `or` only evaluate their right operand if the left one doesn't decide the result.
         match operation.operator {
-            BinaryOperator::Plus => left_value.add(&right_value),
-            _ => panic!("Operator {:?} not supported", operation.operator)
+            BinaryOperator::And if !left_value.to_boolean() => return Ok(Value::Boolean(false)),
+            BinaryOperator::Or if left_value.to_boolean() => return Ok(Value::Boolean(true)),
+            _ => {}
         }
+
+        let right_value = self.interpret_node(operation.right.deref(), context)?;
+        let ordering = || left_value.compare(&right_value);
+
+        let arithmetic = match operation.operator {
+            BinaryOperator::Plus => left_value.add(&right_value),
+            BinaryOperator::Minus => left_value.subtract(&right_value),
+            BinaryOperator::Multiply => left_value.multiply(&right_value),
+            BinaryOperator::Divide => left_value.divide(&right_value),
+            BinaryOperator::Modulo => left_value.remainder(&right_value),
+
+            BinaryOperator::Equal => Ok(Value::Boolean(left_value.equals(&right_value))),
+            BinaryOperator::NotEqual => Ok(Value::Boolean(!left_value.equals(&right_value))),
+            BinaryOperator::Less => Ok(Value::Boolean(ordering().is_some_and(Ordering::is_lt))),
+            BinaryOperator::LessEqual => {
+                Ok(Value::Boolean(ordering().is_some_and(Ordering::is_le)))
+            }
+            BinaryOperator::Greater => Ok(Value::Boolean(ordering().is_some_and(Ordering::is_gt))),
+            BinaryOperator::GreaterEqual => {
+                Ok(Value::Boolean(ordering().is_some_and(Ordering::is_ge)))
+            }
+
+            BinaryOperator::Range | BinaryOperator::RangeInclusive => Ok(Value::Range {
+                start: left_value.to_integer(),
+                end: right_value.to_integer(),
+                inclusive: operation.operator == BinaryOperator::RangeInclusive,
+            }),
+
+            // The left operand didn't decide the result, so the right operand is the result.
+            BinaryOperator::And | BinaryOperator::Or => {
+                Ok(Value::Boolean(right_value.to_boolean()))
+            }
+        };
+
+        arithmetic.map_err(|error| {
+            InterpreterError::from_arithmetic(error, span, operation.right.span()).into()
+        })
     }
 }
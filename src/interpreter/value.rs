@@ -1,27 +1,283 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::rc::Rc;
+
+use super::context::Closure;
+use super::error::ArithmeticError;
+
+// The longest string (in bytes) that repeating a string can make.
+pub const MAX_STRING_LENGTH: usize = 1 << 26;
+
 #[derive(Debug, Clone)]
 pub enum Value {
     String(String),
-    Integer(u32),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+
+    // The integers from `start` up to `end`, which is only included if the range is inclusive.
+    Range {
+        start: i64,
+        end: i64,
+        inclusive: bool,
+    },
+
+    // Lists are shared rather than copied, so assigning to an element changes the list for everything that holds it.
+    List(Rc<RefCell<Vec<Value>>>),
+
+    // A function, along with the scope that it can see.
+    Closure(Closure),
+
     None,
 }
 
 impl Value {
-    pub fn add(&self, other: &Value) -> Value {
+    // Strings are printed without quotes, everything else is printed the same as its `Display` form.
+    pub fn to_print_string(&self) -> String {
         match self {
-            Value::Integer(_) => self.add_integers(other),
-            _ => panic!("Unable to add {:?} and {:?}", self, other),
+            Value::String(value) => value.clone(),
+            _ => self.to_string(),
         }
     }
 
-    fn add_integers(&self, other: &Value) -> Value {
-        let Value::Integer(self_value) = self else {
-            panic!("Expected {:?} to be Integer!", self);
-        };
+    // Adds two numbers, or concatenates two strings.
+    pub fn add(&self, other: &Value) -> Result<Value, ArithmeticError> {
+        if let (Value::String(left), Value::String(right)) = (self, other) {
+            return Ok(Value::String(format!("{}{}", left, right)));
+        }
 
-        let Value::Integer(other_value) = other else {
-            panic!("Expected {:?} to be Integer!", other);
+        self.numeric_operation(other, i64::checked_add, |left, right| left + right)
+    }
+
+    pub fn subtract(&self, other: &Value) -> Result<Value, ArithmeticError> {
+        self.numeric_operation(other, i64::checked_sub, |left, right| left - right)
+    }
+
+    // Multiplies two numbers, or repeats a string (e.g. `"ab" * 3` or `3 * "ab"`).
+    pub fn multiply(&self, other: &Value) -> Result<Value, ArithmeticError> {
+        match (self, other) {
+            (Value::String(string), Value::Integer(count))
+            | (Value::Integer(count), Value::String(string)) => Self::repeat(string, *count),
+            _ => self.numeric_operation(other, i64::checked_mul, |left, right| left * right),
+        }
+    }
+
+    fn repeat(string: &str, count: i64) -> Result<Value, ArithmeticError> {
+        let Ok(count) = usize::try_from(count) else {
+            return Err(ArithmeticError::NegativeRepetition(count));
         };
 
-        Value::Integer(self_value + other_value)
+        // Repeating a string past the longest string that can be made counts as an overflow, rather than running out
+        // of memory.
+        match string.len().checked_mul(count) {
+            Some(length) if length <= MAX_STRING_LENGTH => {}
+            _ => return Err(ArithmeticError::Overflow),
+        }
+
+        Ok(Value::String(string.repeat(count)))
     }
-}
\ No newline at end of file
+
+    // Integer division truncates towards zero, so `-7 / 2` is `-3`.
+    pub fn divide(&self, other: &Value) -> Result<Value, ArithmeticError> {
+        other.check_divisor()?;
+        self.numeric_operation(other, i64::checked_div, |left, right| left / right)
+    }
+
+    // The remainder has the same sign as the dividend, so that `a == (a / b) * b + a % b` (e.g. `-7 % 2` is `-1`).
+    // It can't overflow once the divisor isn't zero: the only case that `checked_rem` rejects is `i64::MIN % -1`,
+    // which is 0 even though the division itself overflows.
+    pub fn remainder(&self, other: &Value) -> Result<Value, ArithmeticError> {
+        other.check_divisor()?;
+        self.numeric_operation(
+            other,
+            |left, right| Some(left.wrapping_rem(right)),
+            |left, right| left % right,
+        )
+    }
+
+    pub fn negate(&self) -> Result<Value, ArithmeticError> {
+        match self {
+            Value::Integer(value) => value
+                .checked_neg()
+                .map(Value::Integer)
+                .ok_or(ArithmeticError::Overflow),
+            Value::Float(value) => Ok(Value::Float(-value)),
+            _ => panic!("Unable to negate {:?}", self),
+        }
+    }
+
+    fn check_divisor(&self) -> Result<(), ArithmeticError> {
+        if self.to_float() == 0.0 {
+            return Err(ArithmeticError::DivisionByZero);
+        }
+
+        Ok(())
+    }
+
+    // Applies an operation to two numbers. Integers use checked arithmetic, and are widened to floats when they are
+    // mixed with one. Floats follow IEEE 754, so they can become infinite but never overflow.
+    fn numeric_operation(
+        &self,
+        other: &Value,
+        integer_operation: fn(i64, i64) -> Option<i64>,
+        float_operation: fn(f64, f64) -> f64,
+    ) -> Result<Value, ArithmeticError> {
+        match (self, other) {
+            (Value::Integer(left), Value::Integer(right)) => integer_operation(*left, *right)
+                .map(Value::Integer)
+                .ok_or(ArithmeticError::Overflow),
+            (Value::Integer(_) | Value::Float(_), Value::Integer(_) | Value::Float(_)) => Ok(
+                Value::Float(float_operation(self.to_float(), other.to_float())),
+            ),
+            _ => panic!(
+                "Unable to apply an arithmetic operation to {:?} and {:?}",
+                self, other
+            ),
+        }
+    }
+
+    pub fn not(&self) -> Value {
+        Value::Boolean(!self.to_boolean())
+    }
+
+    pub fn to_boolean(&self) -> bool {
+        match self {
+            Value::Boolean(value) => *value,
+            _ => panic!("Expected {:?} to be Boolean!", self),
+        }
+    }
+
+    // Numbers are equal if they have the same value, even if one is an Integer and the other is a Float.
+    pub fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Integer(left), Value::Integer(right)) => left == right,
+            (Value::Integer(_) | Value::Float(_), Value::Integer(_) | Value::Float(_)) => {
+                self.to_float() == other.to_float()
+            }
+            (Value::String(left), Value::String(right)) => left == right,
+            (Value::Boolean(left), Value::Boolean(right)) => left == right,
+            (
+                Value::Range {
+                    start,
+                    end,
+                    inclusive,
+                },
+                Value::Range {
+                    start: other_start,
+                    end: other_end,
+                    inclusive: other_inclusive,
+                },
+            ) => start == other_start && end == other_end && inclusive == other_inclusive,
+            (Value::List(left), Value::List(right)) => {
+                let (left, right) = (left.borrow(), right.borrow());
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .zip(right.iter())
+                        .all(|(left, right)| left.equals(right))
+            }
+            (Value::None, Value::None) => true,
+            _ => false,
+        }
+    }
+
+    // Returns `None` if the values can't be ordered, which includes comparisons with `NaN`.
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Integer(left), Value::Integer(right)) => Some(left.cmp(right)),
+            (Value::Integer(_) | Value::Float(_), Value::Integer(_) | Value::Float(_)) => {
+                self.to_float().partial_cmp(&other.to_float())
+            }
+
+            // Strings are ordered by their characters' code points, one character at a time.
+            (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+
+            _ => panic!("Unable to compare {:?} and {:?}", self, other),
+        }
+    }
+
+    // The values that a for loop visits, in order.
+    pub fn elements(&self) -> Box<dyn Iterator<Item = Value>> {
+        match *self {
+            Value::Range {
+                start,
+                end,
+                inclusive: false,
+            } => Box::new((start..end).map(Value::Integer)),
+            Value::Range {
+                start,
+                end,
+                inclusive: true,
+            } => Box::new((start..=end).map(Value::Integer)),
+
+            // The loop visits the elements that the list had when it started, even if the body changes the list.
+            Value::List(ref elements) => Box::new(elements.borrow().clone().into_iter()),
+
+            _ => panic!("Unable to iterate over {:?}", self),
+        }
+    }
+
+    pub fn to_list(&self) -> Rc<RefCell<Vec<Value>>> {
+        match self {
+            Value::List(elements) => elements.clone(),
+            _ => panic!("Expected {:?} to be a list!", self),
+        }
+    }
+
+    pub fn to_integer(&self) -> i64 {
+        match self {
+            Value::Integer(value) => *value,
+            _ => panic!("Expected {:?} to be Integer!", self),
+        }
+    }
+
+    // Widens an Integer to a Float, which is how integers are treated when they are mixed with floats.
+    pub fn to_float(&self) -> f64 {
+        match self {
+            Value::Integer(value) => *value as f64,
+            Value::Float(value) => *value,
+            _ => panic!("Expected {:?} to be a number!", self),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(value) => write!(f, "{:?}", value),
+            Value::Integer(value) => write!(f, "{}", value),
+
+            // Floats always have a fraction or exponent (e.g. `1.0`), so they can't be mistaken for integers.
+            Value::Float(value) => write!(f, "{:?}", value),
+            Value::Boolean(value) => write!(f, "{}", value),
+            Value::Range {
+                start,
+                end,
+                inclusive: false,
+            } => write!(f, "{}..{}", start, end),
+            Value::Range {
+                start,
+                end,
+                inclusive: true,
+            } => write!(f, "{}..={}", start, end),
+            Value::List(elements) => {
+                write!(f, "[")?;
+                for (index, element) in elements.borrow().iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{}", element)?;
+                }
+
+                write!(f, "]")
+            }
+            Value::Closure(closure) => match &closure.function.name {
+                Some(name) => write!(f, "<fn {}>", name),
+                None => write!(f, "<fn>"),
+            },
+            Value::None => write!(f, "None"),
+        }
+    }
+}
@@ -1,5 +1,29 @@
-#[derive(Debug, Clone, PartialEq)]
+// Identifies a file that has been added to a `SourceMap`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FileId(pub usize);
+
+// A position in a script, resolved from a byte offset by a `SourceMap`. Both the line and the column are 0-based.
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Location {
     pub line: usize,
     pub column: usize,
 }
+
+// A range of bytes in a file, from `start` up to (but not including) `end`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Span {
+    pub file: FileId,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(file: FileId, start: usize, end: usize) -> Self {
+        Self { file, start, end }
+    }
+
+    // Returns a span that covers both this span and `other`, which must be in the same file.
+    pub fn to(&self, other: &Span) -> Span {
+        Span::new(self.file, self.start, other.end)
+    }
+}
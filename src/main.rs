@@ -1,49 +1,233 @@
-use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+use std::{env, fs, thread};
 
+use cli::{Command, USAGE};
+use diagnostic::Diagnostic;
 use interpreter::Interpreter;
-use parser::Parser;
-use tokenizer::Tokenizer;
+use location::FileId;
+use parser::{Node, Parser};
+use repl::Repl;
+use source_map::{ColumnUnit, SourceMap};
+use tests::TestRunner;
+use tokenizer::{Token, Tokenizer, TokenizerError};
 use typechecker::Typechecker;
 
+mod cli;
+mod diagnostic;
 mod interpreter;
 mod location;
 mod parser;
+mod repl;
+mod source_map;
 mod stream;
-// mod tests;
+mod tests;
 mod tokenizer;
 mod typechecker;
 
-fn main() {
-    let script = fs::read_to_string("examples/addition.caoi").unwrap();
-    let mut tokenizer = Tokenizer::new(script.chars().collect());
-
-    let tokens = tokenizer.process();
-    println!("Tokens: {:#?}", tokens);
-
-    let mut parser = Parser::new(tokens);
-    let tree = parser.try_parse().unwrap();
-    println!("Parser Output: {:#?}", tree);
-
-    let mut typechecker = Typechecker::new(tree.clone());
-    let errors = typechecker.check();
-    if errors.is_empty() {
-        println!("Typechecker is happy!");
-    } else {
-        println!("Typechecker is sad :(");
-        for error in errors {
-            let line = error.location.line;
-            let column = error.location.column;
-
-            println!("====================");
-            println!("Error at line {} column {}:", line, column);
-            println!("{}", script.lines().nth(line).unwrap());
-            println!("{}^", " ".repeat(column));
-            println!("{}{}", " ".repeat(column), error.message);
+// The script could not be checked or run, e.g. because of a syntax or type error.
+const EXIT_FAILURE: u8 = 1;
+
+// The command line was invalid, or the script could not be read.
+const EXIT_USAGE: u8 = 2;
+
+// The interpreter recurses for every nested expression and function call, so it needs more than the default stack to
+// reach `MAX_CALL_DEPTH` in deeply nested functions.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+fn main() -> ExitCode {
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run_command)
+        .expect("Unable to start the main thread")
+        .join()
+        .unwrap_or(ExitCode::FAILURE)
+}
+
+fn run_command() -> ExitCode {
+    let command = match Command::parse(env::args().skip(1)) {
+        Ok(command) => command,
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    let result = match command {
+        Command::Run {
+            path,
+            dump_context,
+            utf16_columns,
+        } => run(&path, dump_context, utf16_columns),
+        Command::Check {
+            path,
+            utf16_columns,
+        } => check(&path, utf16_columns),
+        Command::Tokens { path } => tokens(&path),
+        Command::Ast { path } => ast(&path),
+        Command::Repl => repl(),
+        Command::Test { directory } => test(&directory),
+        Command::Help => {
+            println!("{}", USAGE);
+            Ok(())
         }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(code) => ExitCode::from(code),
+    }
+}
+
+fn run(path: &Path, dump_context: bool, utf16_columns: bool) -> Result<(), u8> {
+    let (mut sources, file) = load_script(path)?;
+    if utf16_columns {
+        sources.column_unit = ColumnUnit::Utf16;
     }
 
-    println!("Executing interpreter...");
+    let tree = typechecked_tree(&sources, file)?;
 
     let mut interpreter = Interpreter::new(tree);
-    interpreter.run();
+    let (context, error) = interpreter.run();
+
+    if dump_context {
+        println!("{}", context);
+    }
+
+    if let Some(error) = error {
+        Diagnostic::from(&error).emit(&sources);
+        return Err(EXIT_FAILURE);
+    }
+
+    Ok(())
+}
+
+fn check(path: &Path, utf16_columns: bool) -> Result<(), u8> {
+    let (mut sources, file) = load_script(path)?;
+    if utf16_columns {
+        sources.column_unit = ColumnUnit::Utf16;
+    }
+
+    typechecked_tree(&sources, file)?;
+
+    Ok(())
+}
+
+fn tokens(path: &Path) -> Result<(), u8> {
+    let (sources, file) = load_script(path)?;
+
+    let mut tokenizer = Tokenizer::new(sources.file(file).source.clone(), file);
+    let (tokens, errors) = tokenizer.process();
+    println!("{:#?}", tokens);
+    println!("{:#?}", tokenizer.comments());
+
+    if emit_all(&errors, &sources) {
+        return Err(EXIT_FAILURE);
+    }
+
+    Ok(())
+}
+
+// The tree is printed even if there were syntax errors, so that the statements that did parse can be inspected.
+fn ast(path: &Path) -> Result<(), u8> {
+    let (sources, file) = load_script(path)?;
+
+    let (tree, has_errors) = syntax_tree(&sources, file);
+    println!("{:#?}", tree);
+
+    if has_errors {
+        return Err(EXIT_FAILURE);
+    }
+
+    Ok(())
+}
+
+fn repl() -> Result<(), u8> {
+    Repl::new().run().map_err(|error| {
+        eprintln!("error: {}", error);
+        EXIT_FAILURE
+    })
+}
+
+fn test(directory: &Path) -> Result<(), u8> {
+    match TestRunner::run_directory(directory) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(EXIT_FAILURE),
+        Err(error) => {
+            eprintln!(
+                "error: Unable to run tests in `{}`: {}",
+                directory.display(),
+                error
+            );
+            Err(EXIT_USAGE)
+        }
+    }
+}
+
+// Reads a script into a new source map.
+fn load_script(path: &Path) -> Result<(SourceMap, FileId), u8> {
+    let script = fs::read_to_string(path).map_err(|error| {
+        eprintln!("error: Unable to read `{}`: {}", path.display(), error);
+        EXIT_USAGE
+    })?;
+
+    let mut sources = SourceMap::new();
+    let file = sources.add(path.display().to_string(), script);
+
+    Ok((sources, file))
+}
+
+fn tokenize(sources: &SourceMap, file: FileId) -> (Vec<Token>, Vec<TokenizerError>) {
+    let mut tokenizer = Tokenizer::new(sources.file(file).source.clone(), file);
+    tokenizer.process()
+}
+
+// Tokenizes and parses a file, reporting any syntax errors that were found by either phase.
+// Returns the statements that parsed successfully, and whether there were any errors.
+pub fn syntax_tree(sources: &SourceMap, file: FileId) -> (Vec<Node>, bool) {
+    let (tokens, tokenizer_errors) = tokenize(sources, file);
+
+    let mut parser = Parser::new(tokens, sources.file(file));
+    let (tree, parser_errors) = parser.parse();
+
+    let has_tokenizer_errors = emit_all(&tokenizer_errors, sources);
+    let has_parser_errors = emit_all(&parser_errors, sources);
+
+    (tree, has_tokenizer_errors || has_parser_errors)
+}
+
+// Tokenizes, parses and typechecks a file, reporting any errors that were found.
+pub fn typechecked_tree(sources: &SourceMap, file: FileId) -> Result<Vec<Node>, u8> {
+    typechecked_tree_with(&mut Typechecker::new(vec![]), sources, file)
+}
+
+// Like `typechecked_tree`, but the file can use anything that `typechecker` has already seen declared.
+pub fn typechecked_tree_with(
+    typechecker: &mut Typechecker,
+    sources: &SourceMap,
+    file: FileId,
+) -> Result<Vec<Node>, u8> {
+    let (tree, has_errors) = syntax_tree(sources, file);
+    if has_errors {
+        return Err(EXIT_FAILURE);
+    }
+
+    let (tree, errors) = typechecker.check_more(tree);
+    if emit_all(&errors, sources) {
+        return Err(EXIT_FAILURE);
+    }
+
+    Ok(tree)
+}
+
+// Emits a diagnostic for each error, returning whether there were any.
+pub fn emit_all<'a, E>(errors: &'a [E], sources: &SourceMap) -> bool
+where
+    Diagnostic: From<&'a E>,
+{
+    for error in errors {
+        Diagnostic::from(error).emit(sources);
+    }
+
+    !errors.is_empty()
 }
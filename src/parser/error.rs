@@ -1,25 +1,62 @@
-use anyhow::Result;
 use thiserror::Error;
 
+use crate::diagnostic::Diagnostic;
+use crate::location::Span;
 use crate::tokenizer::Token;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum ParserError {
     #[error("Reached unexpected end of file")]
-    UnexpectedEOF,
+    UnexpectedEOF(Span),
 
-    #[error("Unknown Token: {0}")]
+    #[error("Expected an expression, found {0}")]
     UnknownToken(Token),
 
-    #[error("Unexpected Token: {0}")]
-    UnexpectedToken(Token),
+    #[error("Expected {0}, found {1}")]
+    ExpectedToken(String, Token),
 
-    #[error("Expected Token: {0}")]
-    ExpectedToken(String),
+    #[error("Expected an expression before `}}`")]
+    IncompleteInterpolation(Span),
+
+    #[error("Invalid assignment target")]
+    InvalidAssignmentTarget(Span),
+
+    // `9223372036854775808` without a minus sign in front of it, since only the negative literal fits in an Integer.
+    #[error("Integer literal `{0}` is out of range")]
+    IntegerOutOfRange(u64, Span),
+}
+
+impl ParserError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParserError::UnexpectedEOF(span) => span.clone(),
+            ParserError::UnknownToken(token) => token.clone().span(),
+            ParserError::ExpectedToken(_, token) => token.clone().span(),
+            ParserError::IncompleteInterpolation(span) => span.clone(),
+            ParserError::InvalidAssignmentTarget(span) => span.clone(),
+            ParserError::IntegerOutOfRange(_, span) => span.clone(),
+        }
+    }
+}
+
+impl From<&ParserError> for Diagnostic {
+    fn from(value: &ParserError) -> Self {
+        let diagnostic = Diagnostic::error(value.to_string()).with_span(value.span());
+
+        match value {
+            ParserError::InvalidAssignmentTarget(_) => diagnostic.with_help(
+                "Only variables and list elements can be assigned to, e.g. `x = 5` or `xs[0] = 5`",
+            ),
+            ParserError::IntegerOutOfRange(..) => {
+                diagnostic.with_note(format!("The largest integer is {}", i64::MAX))
+            }
+            _ => diagnostic,
+        }
+    }
 }
 
-impl<T> From<ParserError> for Result<T> {
+impl<T> From<ParserError> for Result<T, ParserError> {
     fn from(value: ParserError) -> Self {
-        Err(anyhow::Error::from(value))
+        Err(value)
     }
 }
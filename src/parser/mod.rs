@@ -1,118 +1,220 @@
-use anyhow::Result;
+use std::mem;
 
-use error::*;
+pub use error::*;
 pub use node::*;
 
-use crate::location::Location;
+use crate::location::Span;
+use crate::source_map::SourceFile;
 use crate::stream::ElementStream;
 use crate::tokenizer::Token::EndOfFile;
-use crate::tokenizer::{Keyword, Token};
+use crate::tokenizer::{Keyword, StringPart, Token};
 
 mod error;
 mod node;
 
-pub struct Parser {
+type Result<T> = std::result::Result<T, ParserError>;
+
+pub struct Parser<'a> {
     stream: ElementStream<Token>,
+
+    // The file that the tokens came from, used to find line boundaries when recovering from an error.
+    file: &'a SourceFile,
+
+    // The number of `{` consumed by the current statement that haven't been closed yet, so that recovering from an
+    // error inside a block can skip the rest of that block.
+    open_braces: usize,
 }
 
-impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Self {
+impl<'a> Parser<'a> {
+    pub fn new(tokens: Vec<Token>, file: &'a SourceFile) -> Self {
         Self {
             stream: ElementStream::new(tokens),
+            file,
+            open_braces: 0,
         }
     }
 
-    pub fn try_parse(&mut self) -> Result<Vec<Node>> {
+    // Parses every statement in the stream. When a statement has a syntax error, the error is recorded and parsing
+    // continues from the next statement, so the returned tree only contains the statements that parsed successfully.
+    pub fn parse(&mut self) -> (Vec<Node>, Vec<ParserError>) {
         let mut nodes = vec![];
+        let mut errors = vec![];
 
-        loop {
-            let Some(token) = self.stream.peek() else {
-                break;
-            };
-
+        while let Some(token) = self.stream.peek() {
             if let EndOfFile(_) = token {
                 break;
             }
 
-            nodes.push(self.try_parse_expression()?);
+            let start = self.stream.index;
+            self.open_braces = 0;
+
+            match self.try_parse_expression() {
+                Ok(node) => nodes.push(node),
+                Err(error) => {
+                    // Always make progress, even if the error happened before any token was consumed.
+                    if self.stream.index == start {
+                        self.consume();
+                    }
+
+                    self.synchronise(self.file.line_index(error.span().start));
+                    errors.push(error);
+                }
+            }
         }
 
-        Ok(nodes)
+        (nodes, errors)
     }
 
+    // Skips tokens until the start of the next statement: a keyword, or the first token on a line after `line`. Any
+    // block that the failed statement opened is skipped to its end first, wherever the statements inside it start.
+    fn synchronise(&mut self, mut line: usize) {
+        while let Some(token) = self.stream.peek() {
+            match token {
+                Token::EndOfFile(_) => break,
+                Token::RightBrace(span) if self.open_braces > 0 => {
+                    line = self.file.line_index(span.start);
+                    self.consume();
+                }
+                _ if self.open_braces > 0 => {
+                    self.consume();
+                }
+                Token::Keyword(keyword, _) if keyword.starts_statement() => break,
+                _ if self.file.line_index(token.clone().span().start) > line => break,
+                _ => {
+                    self.consume();
+                }
+            }
+        }
+    }
+
+    // <expression> = <expression>
     fn try_parse_expression(&mut self) -> Result<Node> {
-        let first_node = self.try_parse_literal()?;
+        let node = self.try_parse_binary_operation_expression(0)?;
+
+        // Assignment binds more loosely than every binary operator.
+        let Ok(Token::Equals(_)) = self.try_peek() else {
+            return Ok(node);
+        };
 
-        // The next token decides what kind of operation we should parse.
-        let token = self.try_peek();
-        if token.is_err() {
-            return Ok(first_node);
+        match node {
+            Node::Reference(identifier, span) => {
+                self.try_consume()?;
+                self.try_parse_assignment_expression(identifier, span)
+            }
+            Node::Index(operation, span) => {
+                self.try_consume()?;
+                self.try_parse_index_assignment_expression(operation, span)
+            }
+            _ => ParserError::InvalidAssignmentTarget(node.span().clone()).into(),
         }
+    }
 
-        let token = token.unwrap();
+    // (LITERAL) (OPERATOR) (LITERAL) ...
+    // Only operators that bind at least as tightly as `minimum_precedence` are parsed, so that the caller can group
+    // the rest of the operators around the returned node.
+    fn try_parse_binary_operation_expression(&mut self, minimum_precedence: u8) -> Result<Node> {
+        let mut left = self.try_parse_unary_operation_expression()?;
 
-        match token {
-            // If the next token is a binary operator operand, we can attempt to parse a binary operator expression.
-            Token::Plus(_) | Token::Asterisk(_) | Token::Slash(_) | Token::Minus(_) => {
-                self.try_consume()?;
-                self.try_parse_binary_operation_expression(first_node, token)
+        while let Ok(token) = self.try_peek() {
+            let Some(operator) = BinaryOperator::from_token(&token) else {
+                break;
+            };
+
+            // Statements aren't separated, so an operator at the start of a line begins a new statement (e.g. `-3`)
+            // rather than continuing the one before it. An operator at the end of a line still continues onto the next.
+            if self.file.line_index(token.span().start) != self.file.line_index(left.span().end) {
+                break;
+            }
+
+            let precedence = operator.precedence();
+            if precedence < minimum_precedence {
+                break;
             }
 
-            // If we don't recognize the next token, we can assume that the expression is complete.
-            _ => Ok(first_node),
+            self.try_consume()?;
+
+            // Every binary operator is left associative, so the right operand can only contain operators that bind
+            // more tightly than this one (e.g. `10 - 2 - 3` is `(10 - 2) - 3`).
+            let right = self.try_parse_binary_operation_expression(precedence + 1)?;
+
+            let span = left.span().to(right.span());
+            let binary_operation = BinaryOperationNode {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+            };
+
+            left = Node::BinaryOperation(binary_operation, span);
         }
+
+        Ok(left)
     }
 
-    // (LITERAL) (OPERAND) (LITERAL)
-    // NOTE: We trust that the caller has checked that `operand` is of the correct type.
-    fn try_parse_binary_operation_expression(
-        &mut self,
-        first_literal: Node,
-        operand: Token,
-    ) -> Result<Node> {
-        let second_literal = self.try_parse_expression()?;
+    // (OPERATOR) (LITERAL)
+    // Prefix operators bind more tightly than every binary operator (e.g. `-a * b` is `(-a) * b`).
+    fn try_parse_unary_operation_expression(&mut self) -> Result<Node> {
+        let token = self.try_peek()?;
+        let Some(operator) = UnaryOperator::from_token(&token) else {
+            return self.try_parse_postfix_expression();
+        };
 
-        let operator = match operand {
-            Token::Plus(_) => BinaryOperator::Plus,
-            Token::Minus(_) => BinaryOperator::Minus,
-            Token::Asterisk(_) => BinaryOperator::Multiply,
-            Token::Slash(_) => BinaryOperator::Divide,
+        self.try_consume()?;
 
-            _ => return ParserError::UnknownToken(operand).into(),
-        };
+        // The smallest integer is parsed as a single literal, since its magnitude on its own is out of range.
+        if let (UnaryOperator::Negate, Ok(Token::Integer(magnitude, end))) =
+            (&operator, self.try_peek())
+        {
+            if magnitude == i64::MIN.unsigned_abs() {
+                self.try_consume()?;
+                let literal = Node::Literal(Literal::Integer(i64::MIN), token.span().to(&end));
+                return self.try_parse_postfix_operations(literal);
+            }
+        }
+
+        let operand = self.try_parse_unary_operation_expression()?;
 
-        let binary_operation = BinaryOperationNode {
-            left: Box::new(first_literal),
+        let span = token.span().to(operand.span());
+        let unary_operation = UnaryOperationNode {
             operator,
-            right: Box::new(second_literal),
+            operand: Box::new(operand),
         };
 
-        Ok(Node::BinaryOperation(binary_operation, operand.location()))
+        Ok(Node::UnaryOperation(unary_operation, span))
     }
 
     fn try_parse_literal(&mut self) -> Result<Node> {
         let token = self.try_consume()?;
 
         let node = match token {
-            Token::Integer(value, location) => Node::Literal(Literal::Integer(value), location),
+            Token::Integer(value, span) => match i64::try_from(value) {
+                Ok(value) => Node::Literal(Literal::Integer(value), span),
+                Err(_) => return ParserError::IntegerOutOfRange(value, span).into(),
+            },
 
-            Token::String(value, location) => Node::Literal(Literal::String(value), location),
+            Token::Float(value, span) => Node::Literal(Literal::Float(value), span),
 
-            Token::Keyword(keyword, location) => match keyword {
-                Keyword::Let => self.try_parse_let_expression(location)?,
-            },
+            Token::Boolean(value, span) => Node::Literal(Literal::Boolean(value), span),
 
-            Token::Identifier(identifier, location) => {
-                let next = self.try_peek()?;
-                match next {
-                    Token::Equals(_) => {
-                        self.try_consume()?;
-                        self.try_parse_assignment_expression(identifier, location)?
-                    }
+            Token::String(value, span) => Node::Literal(Literal::String(value), span),
 
-                    _ => Node::Reference(identifier, location),
-                }
-            }
+            Token::InterpolatedString(parts, span) => self.try_parse_interpolation(parts, span)?,
+
+            Token::Keyword(Keyword::Let, span) => self.try_parse_let_expression(span, false)?,
+            Token::Keyword(Keyword::Var, span) => self.try_parse_let_expression(span, true)?,
+            Token::Keyword(Keyword::Print, span) => self.try_parse_print_expression(span)?,
+            Token::Keyword(Keyword::If, span) => self.try_parse_if_expression(span)?,
+            Token::Keyword(Keyword::While, span) => self.try_parse_while_expression(span)?,
+            Token::Keyword(Keyword::For, span) => self.try_parse_for_expression(span)?,
+            Token::Keyword(Keyword::Fn, span) => self.try_parse_function(span)?,
+            Token::Keyword(Keyword::Return, span) => self.try_parse_return_expression(span)?,
+            Token::Keyword(Keyword::Break, span) => Node::Break(span),
+            Token::Keyword(Keyword::Continue, span) => Node::Continue(span),
+
+            Token::Identifier(identifier, span) => Node::Reference(identifier, span),
+
+            Token::LeftParenthesis(span) => self.try_parse_group_expression(span)?,
+            Token::LeftBrace(span) => self.try_parse_block_statements(span)?,
+            Token::LeftBracket(span) => self.try_parse_list_expression(span)?,
 
             _ => return ParserError::UnknownToken(token).into(),
         };
@@ -120,34 +222,110 @@ impl Parser {
         Ok(node)
     }
 
+    // (<expression>)
+    fn try_parse_group_expression(&mut self, span: Span) -> Result<Node> {
+        let expression = self.try_parse_expression()?;
+
+        let token = self.try_consume()?;
+        let Token::RightParenthesis(end) = token else {
+            return ParserError::ExpectedToken("`)`".into(), token).into();
+        };
+
+        Ok(Node::Group(Box::new(expression), span.to(&end)))
+    }
+
+    // [<expression>, ...]
+    fn try_parse_list_expression(&mut self, span: Span) -> Result<Node> {
+        let (elements, end) =
+            self.try_parse_list(Token::RightBracket, Self::try_parse_expression)?;
+        Ok(Node::List(elements, span.to(&end)))
+    }
+
+    // "<text>${<expression>}<text>"
+    fn try_parse_interpolation(&mut self, parts: Vec<StringPart>, span: Span) -> Result<Node> {
+        let mut nodes = vec![];
+
+        for part in parts {
+            match part {
+                StringPart::Text(text) => nodes.push(InterpolationPart::Text(text)),
+                StringPart::Expression(tokens, _) => {
+                    let mut parser = Parser::new(tokens, self.file);
+
+                    // Running out of tokens means that the `}` was reached, so the input isn't just incomplete.
+                    let expression = match parser.try_parse_expression() {
+                        Err(ParserError::UnexpectedEOF(span)) => {
+                            return ParserError::IncompleteInterpolation(span).into()
+                        }
+                        result => result?,
+                    };
+
+                    // The expression has to use every token before the `}`.
+                    let token = parser.try_peek()?;
+                    if !matches!(token, EndOfFile(_)) {
+                        return ParserError::ExpectedToken("`}`".into(), token).into();
+                    }
+
+                    nodes.push(InterpolationPart::Expression(expression));
+                }
+            }
+        }
+
+        Ok(Node::Interpolation(nodes, span))
+    }
+
     // <identifier> = <expression>
-    fn try_parse_assignment_expression(
-        &mut self,
-        identifier: String,
-        location: Location,
-    ) -> Result<Node> {
+    fn try_parse_assignment_expression(&mut self, identifier: String, span: Span) -> Result<Node> {
         let expression = self.try_parse_expression()?;
 
+        let span = span.to(expression.span());
         let assignment_operation = AssignmentOperationNode {
             identifier: VariableReference::Unresolved(identifier),
             expression: Box::new(expression),
         };
 
-        Ok(Node::AssignmentOperation(assignment_operation, location))
+        Ok(Node::AssignmentOperation(assignment_operation, span))
     }
 
-    // let <identifier>(: <type>)= <expression>
-    fn try_parse_let_expression(&mut self, location: Location) -> Result<Node> {
+    // <expression>[<expression>] = <expression>
+    fn try_parse_index_assignment_expression(
+        &mut self,
+        operation: IndexNode,
+        span: Span,
+    ) -> Result<Node> {
+        let expression = self.try_parse_expression()?;
+
+        let span = span.to(expression.span());
+        let index_assignment = IndexAssignmentNode {
+            target: operation.target,
+            index: operation.index,
+            expression: Box::new(expression),
+            element_type: None,
+        };
+
+        Ok(Node::IndexAssignment(index_assignment, span))
+    }
+
+    // let (mut) <identifier>(: <type>)= <expression>
+    // var <identifier>(: <type>)= <expression>
+    fn try_parse_let_expression(&mut self, span: Span, mut mutable: bool) -> Result<Node> {
+        // `let mut` is the same as `var`.
+        if let (false, Token::Keyword(Keyword::Mut, _)) = (mutable, self.try_peek()?) {
+            self.try_consume()?;
+            mutable = true;
+        }
+
         let name_identifier = self.try_consume_identifier()?;
 
         // If the next token is an equals sign, we can parse the expression.
         // If the next token is a colon, we can parse the type and then the expression.
         let token = self.try_consume()?;
         match token {
-            Token::Equals(_) => self.try_parse_inferred_let_expression(name_identifier, location),
-            Token::Colon(_) => self.try_parse_typed_let_expression(name_identifier, location),
+            Token::Equals(_) => {
+                self.try_parse_inferred_let_expression(name_identifier, mutable, span)
+            }
+            Token::Colon(_) => self.try_parse_typed_let_expression(name_identifier, mutable, span),
 
-            _ => ParserError::UnexpectedToken(token).into(),
+            _ => ParserError::ExpectedToken("`=` or `:`".into(), token).into(),
         }
     }
 
@@ -155,43 +333,368 @@ impl Parser {
     fn try_parse_inferred_let_expression(
         &mut self,
         name_identifier: String,
-        location: Location,
+        mutable: bool,
+        span: Span,
     ) -> Result<Node> {
         let expression = self.try_parse_expression()?;
 
+        let span = span.to(expression.span());
         let let_operation = LetOperationNode {
             name_identifier,
+            mutable,
             type_identifier: None,
             expression: Box::new(expression),
         };
 
-        Ok(Node::LetOperation(let_operation, location))
+        Ok(Node::LetOperation(let_operation, span))
     }
 
     // let <identifier>: <type> = <expression>
     fn try_parse_typed_let_expression(
         &mut self,
         name_identifier: String,
-        location: Location,
+        mutable: bool,
+        span: Span,
     ) -> Result<Node> {
-        // The identifier denotes what type the expression result should be.
-        let type_identifier = self.try_consume_identifier()?;
+        // The type that the expression result should be.
+        let type_identifier = self.try_parse_type()?;
 
         // Equals indicates that an expression is next.
         let token = self.try_consume()?;
         let Token::Equals(_) = token else {
-            return ParserError::ExpectedToken("=".into()).into();
+            return ParserError::ExpectedToken("`=`".into(), token).into();
         };
 
         let expression = self.try_parse_expression()?;
 
+        let span = span.to(expression.span());
         let let_operation = LetOperationNode {
             name_identifier,
+            mutable,
             type_identifier: Some(type_identifier),
             expression: Box::new(expression),
         };
 
-        Ok(Node::LetOperation(let_operation, location))
+        Ok(Node::LetOperation(let_operation, span))
+    }
+
+    // print <expression>
+    fn try_parse_print_expression(&mut self, span: Span) -> Result<Node> {
+        let expression = self.try_parse_expression()?;
+
+        let span = span.to(expression.span());
+        Ok(Node::Print(Box::new(expression), span))
+    }
+
+    // if <expression> { ... } (else if <expression> { ... })* (else { ... })
+    fn try_parse_if_expression(&mut self, span: Span) -> Result<Node> {
+        let condition = self.try_parse_expression()?;
+        let then_branch = self.try_parse_block()?;
+
+        let else_branch = match self.try_peek()? {
+            Token::Keyword(Keyword::Else, _) => {
+                self.try_consume()?;
+
+                // `else if` is an if expression nested in the else branch.
+                let branch = match self.try_peek()? {
+                    Token::Keyword(Keyword::If, span) => {
+                        self.try_consume()?;
+                        self.try_parse_if_expression(span)?
+                    }
+                    _ => self.try_parse_block()?,
+                };
+
+                Some(Box::new(branch))
+            }
+            _ => None,
+        };
+
+        let end = match &else_branch {
+            Some(branch) => branch.span(),
+            None => then_branch.span(),
+        };
+
+        let span = span.to(end);
+        let if_operation = IfNode {
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch,
+        };
+
+        Ok(Node::If(if_operation, span))
+    }
+
+    // while <expression> { ... }
+    fn try_parse_while_expression(&mut self, span: Span) -> Result<Node> {
+        let condition = self.try_parse_expression()?;
+        let body = self.try_parse_block()?;
+
+        let span = span.to(body.span());
+        let while_operation = WhileNode {
+            condition: Box::new(condition),
+            body: Box::new(body),
+        };
+
+        Ok(Node::While(while_operation, span))
+    }
+
+    // for <identifier> in <expression> { ... }
+    fn try_parse_for_expression(&mut self, span: Span) -> Result<Node> {
+        let variable = self.try_consume_identifier()?;
+
+        let token = self.try_consume()?;
+        let Token::Keyword(Keyword::In, _) = token else {
+            return ParserError::ExpectedToken("`in`".into(), token).into();
+        };
+
+        let iterable = self.try_parse_expression()?;
+        let body = self.try_parse_block()?;
+
+        let span = span.to(body.span());
+        let for_operation = ForNode {
+            variable,
+            iterable: Box::new(iterable),
+            body: Box::new(body),
+        };
+
+        Ok(Node::For(for_operation, span))
+    }
+
+    // fn <identifier>(<identifier>: <type>, ...)(: <type>) { ... }
+    // fn(<identifier>: <type>, ...)(: <type>) { ... }
+    fn try_parse_function(&mut self, span: Span) -> Result<Node> {
+        // An anonymous function goes straight to its parameters.
+        let name = match self.try_peek()? {
+            Token::LeftParenthesis(_) => None,
+            _ => Some(self.try_consume_identifier()?),
+        };
+
+        let token = self.try_consume()?;
+        let Token::LeftParenthesis(_) = token else {
+            return ParserError::ExpectedToken("`(`".into(), token).into();
+        };
+
+        let (parameters, _) =
+            self.try_parse_list(Token::RightParenthesis, Self::try_parse_parameter)?;
+
+        let return_type = match self.try_peek()? {
+            Token::Colon(_) => {
+                self.try_consume()?;
+                Some(self.try_parse_type()?)
+            }
+            _ => None,
+        };
+
+        let body = self.try_parse_block()?;
+
+        let span = span.to(body.span());
+        let function = FunctionNode {
+            name,
+            parameters,
+            return_type,
+            body: Box::new(body),
+        };
+
+        Ok(Node::Function(function, span))
+    }
+
+    // <identifier>: <type>
+    fn try_parse_parameter(&mut self) -> Result<Parameter> {
+        let token = self.try_consume()?;
+        let Token::Identifier(name, start) = token else {
+            return ParserError::ExpectedToken("a parameter name".into(), token).into();
+        };
+
+        let token = self.try_consume()?;
+        let Token::Colon(_) = token else {
+            return ParserError::ExpectedToken("`:`".into(), token).into();
+        };
+
+        let type_identifier = self.try_parse_type()?;
+
+        let span = start.to(type_identifier.span());
+        Ok(Parameter {
+            name,
+            type_identifier,
+            span,
+        })
+    }
+
+    // <identifier>
+    // <identifier><<type>, ...>
+    // (<type>, ...) -> <type>
+    fn try_parse_type(&mut self) -> Result<TypeAnnotation> {
+        let token = self.try_consume()?;
+        match token {
+            Token::Identifier(name, span) => {
+                let Token::Less(_) = self.try_peek()? else {
+                    return Ok(TypeAnnotation::Named(name, span));
+                };
+
+                self.try_consume()?;
+                let (arguments, end) = self.try_parse_list(Token::Greater, Self::try_parse_type)?;
+
+                Ok(TypeAnnotation::Generic {
+                    name,
+                    arguments,
+                    span: span.to(&end),
+                })
+            }
+
+            Token::LeftParenthesis(start) => {
+                let (parameters, _) =
+                    self.try_parse_list(Token::RightParenthesis, Self::try_parse_type)?;
+
+                let token = self.try_consume()?;
+                let Token::Arrow(_) = token else {
+                    return ParserError::ExpectedToken("`->`".into(), token).into();
+                };
+
+                let return_type = self.try_parse_type()?;
+
+                let span = start.to(return_type.span());
+                Ok(TypeAnnotation::Function {
+                    parameters,
+                    return_type: Box::new(return_type),
+                    span,
+                })
+            }
+
+            _ => ParserError::ExpectedToken("a type".into(), token).into(),
+        }
+    }
+
+    // <expression>(<expression>, ...)
+    // <expression>[<expression>]
+    // Calls and indexing bind more tightly than prefix operators (e.g. `-f(x)` is `-(f(x))`), and can be chained (e.g.
+    // `f(1)(2)` calls the function that `f(1)` returns, and `grid[0][1]` indexes into the first list in `grid`).
+    fn try_parse_postfix_expression(&mut self) -> Result<Node> {
+        let node = self.try_parse_literal()?;
+        self.try_parse_postfix_operations(node)
+    }
+
+    // The calls and indexing that follow an expression that has already been parsed.
+    fn try_parse_postfix_operations(&mut self, mut node: Node) -> Result<Node> {
+        loop {
+            // A call's arguments and an index have to start on the same line as the expression before them, so that a
+            // group or list on the next line is still a separate statement.
+            let token = self.try_peek()?;
+            if self.file.line_index(token.clone().span().start)
+                != self.file.line_index(node.span().end)
+            {
+                break;
+            }
+
+            node = match token {
+                Token::LeftParenthesis(_) => {
+                    self.try_consume()?;
+                    let (arguments, end) =
+                        self.try_parse_list(Token::RightParenthesis, Self::try_parse_expression)?;
+
+                    let span = node.span().to(&end);
+                    let call = CallNode {
+                        callee: Box::new(node),
+                        arguments,
+                    };
+
+                    Node::Call(call, span)
+                }
+
+                Token::LeftBracket(_) => {
+                    self.try_consume()?;
+                    let index = self.try_parse_expression()?;
+
+                    let token = self.try_consume()?;
+                    let Token::RightBracket(end) = token else {
+                        return ParserError::ExpectedToken("`]`".into(), token).into();
+                    };
+
+                    let span = node.span().to(&end);
+                    let operation = IndexNode {
+                        target: Box::new(node),
+                        index: Box::new(index),
+                    };
+
+                    Node::Index(operation, span)
+                }
+
+                _ => break,
+            };
+        }
+
+        Ok(node)
+    }
+
+    // <item>, ... <closing>
+    // The items of a list whose opening delimiter has already been consumed, along with the span of the `closing`
+    // token (e.g. `)` for a call's arguments).
+    fn try_parse_list<T>(
+        &mut self,
+        closing: fn(Span) -> Token,
+        parse_item: fn(&mut Self) -> Result<T>,
+    ) -> Result<(Vec<T>, Span)> {
+        let closing = closing(Span::default());
+        let is_closing = |token: &Token| mem::discriminant(token) == mem::discriminant(&closing);
+
+        let mut items = vec![];
+
+        loop {
+            let token = self.try_peek()?;
+            if is_closing(&token) {
+                self.try_consume()?;
+                return Ok((items, token.span()));
+            }
+
+            items.push(parse_item(self)?);
+
+            // Every item but the last is followed by a comma.
+            match self.try_peek()? {
+                Token::Comma(_) => {
+                    self.try_consume()?;
+                }
+                token if is_closing(&token) => {}
+                token => {
+                    return ParserError::ExpectedToken(format!("`,` or {}", closing), token).into()
+                }
+            }
+        }
+    }
+
+    // return (<expression>)
+    // The value is left out when the `return` is the last thing in a block.
+    fn try_parse_return_expression(&mut self, span: Span) -> Result<Node> {
+        if matches!(self.try_peek()?, Token::RightBrace(_) | Token::EndOfFile(_)) {
+            return Ok(Node::Return(None, span));
+        }
+
+        let expression = self.try_parse_expression()?;
+
+        let span = span.to(expression.span());
+        Ok(Node::Return(Some(Box::new(expression)), span))
+    }
+
+    // { <expression>* }
+    fn try_parse_block(&mut self) -> Result<Node> {
+        let token = self.try_consume()?;
+        let Token::LeftBrace(start) = token else {
+            return ParserError::ExpectedToken("`{`".into(), token).into();
+        };
+
+        self.try_parse_block_statements(start)
+    }
+
+    // The statements of a block and its closing `}`, once the opening `{` has been consumed.
+    fn try_parse_block_statements(&mut self, start: Span) -> Result<Node> {
+        let mut nodes = vec![];
+
+        loop {
+            if let Token::RightBrace(end) = self.try_peek()? {
+                self.try_consume()?;
+                return Ok(Node::Block(nodes, start.to(&end)));
+            }
+
+            nodes.push(self.try_parse_expression()?);
+        }
     }
 
     // Attempts to consume and parse an identifier token.
@@ -199,23 +702,40 @@ impl Parser {
         let token = self.try_consume()?;
         let identifier = match token {
             Token::Identifier(value, _) => value,
-            _ => return ParserError::UnexpectedToken(token).into(),
+            _ => return ParserError::ExpectedToken("an identifier".into(), token).into(),
         };
 
         Ok(identifier)
     }
 
+    // The end of file token is never consumed, so that callers can tell an incomplete input apart from a bad one.
     fn try_consume(&mut self) -> Result<Token> {
-        let Some(token) = self.stream.consume() else {
-            return ParserError::UnexpectedEOF.into();
-        };
+        let token = self.try_peek()?;
+
+        if let EndOfFile(span) = token {
+            return ParserError::UnexpectedEOF(span).into();
+        }
 
+        self.consume();
         Ok(token)
     }
 
+    // Consumes the next token, keeping count of the blocks that are still open.
+    fn consume(&mut self) -> Option<Token> {
+        let token = self.stream.consume();
+
+        match token {
+            Some(Token::LeftBrace(_)) => self.open_braces += 1,
+            Some(Token::RightBrace(_)) => self.open_braces = self.open_braces.saturating_sub(1),
+            _ => {}
+        }
+
+        token
+    }
+
     fn try_peek(&mut self) -> Result<Token> {
         let Some(token) = self.stream.peek() else {
-            return ParserError::UnexpectedEOF.into();
+            return ParserError::UnexpectedEOF(Span::default()).into();
         };
 
         Ok(token)
@@ -1,53 +1,445 @@
-use crate::location::Location;
+use std::fmt::{self, Display, Formatter};
+
+use crate::location::Span;
+use crate::tokenizer::{Keyword, Token};
 use crate::typechecker::Type;
 
 #[derive(Debug, Clone)]
 pub enum Literal {
-    Integer(u32),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
     String(String),
 }
 
 #[derive(Debug, Clone)]
 pub enum Node {
-    Literal(Literal, Location),
+    Literal(Literal, Span),
 
     // A binary operation (e.g. 1 + 3)
-    BinaryOperation(BinaryOperationNode, Location),
+    BinaryOperation(BinaryOperationNode, Span),
+
+    // A prefix operation (e.g. -x or not y)
+    UnaryOperation(UnaryOperationNode, Span),
+
+    // An expression in parentheses (e.g. (1 + 3)), kept so that tools can reproduce the source
+    Group(Box<Node>, Span),
 
     // A let operation (e.g. let x: Integer = 1)
-    LetOperation(LetOperationNode, Location),
+    LetOperation(LetOperationNode, Span),
 
     // An assignment operation (x = 5)
-    AssignmentOperation(AssignmentOperationNode, Location),
+    AssignmentOperation(AssignmentOperationNode, Span),
+
+    // An assignment to an element of a list (e.g. xs[0] = 5)
+    IndexAssignment(IndexAssignmentNode, Span),
 
     // A reference to an identifier
-    Reference(String, Location),
+    Reference(String, Span),
+
+    // A print statement (e.g. print x + 1)
+    Print(Box<Node>, Span),
+
+    // A string with interpolated expressions (e.g. "Hello ${name}")
+    Interpolation(Vec<InterpolationPart>, Span),
+
+    // A list of statements in braces, which has its own scope (e.g. `{ let x = 1 }`). Its value is the last statement.
+    Block(Vec<Node>, Span),
+
+    // An if expression (e.g. if x > 1 { x } else { 1 })
+    If(IfNode, Span),
+
+    // A while loop (e.g. while x < 10 { x = x + 1 })
+    While(WhileNode, Span),
+
+    // A for loop over a range or collection (e.g. for i in 0..10 { print i })
+    For(ForNode, Span),
+
+    // A function declaration (e.g. fn add(a: Integer, b: Integer): Integer { return a + b }), or an anonymous function
+    // without a name (e.g. fn(x: Integer): Integer { x + 1 })
+    Function(FunctionNode, Span),
+
+    // A call to a function (e.g. add(1, 2) or make_adder(1)(2))
+    Call(CallNode, Span),
+
+    // A list literal (e.g. [1, 2, 3])
+    List(Vec<Node>, Span),
+
+    // An element of a list (e.g. xs[0])
+    Index(IndexNode, Span),
+
+    // Leaves the current function, with an optional value (e.g. return x + 1)
+    Return(Option<Box<Node>>, Span),
+
+    // Leaves the innermost loop
+    Break(Span),
+
+    // Skips to the next iteration of the innermost loop
+    Continue(Span),
 }
 
 impl Node {
-    pub fn location(&self) -> &Location {
+    pub fn span(&self) -> &Span {
         match self {
-            Node::Literal(_, location) => location,
-            Node::BinaryOperation(_, location) => location,
-            Node::LetOperation(_, location) => location,
-            Node::AssignmentOperation(_, location) => location,
-            Node::Reference(_, location) => location,
+            Node::Literal(_, span) => span,
+            Node::BinaryOperation(_, span) => span,
+            Node::UnaryOperation(_, span) => span,
+            Node::Group(_, span) => span,
+            Node::LetOperation(_, span) => span,
+            Node::AssignmentOperation(_, span) => span,
+            Node::IndexAssignment(_, span) => span,
+            Node::Reference(_, span) => span,
+            Node::Print(_, span) => span,
+            Node::Interpolation(_, span) => span,
+            Node::Block(_, span) => span,
+            Node::If(_, span) => span,
+            Node::While(_, span) => span,
+            Node::For(_, span) => span,
+            Node::Function(_, span) => span,
+            Node::Call(_, span) => span,
+            Node::List(_, span) => span,
+            Node::Index(_, span) => span,
+            Node::Return(_, span) => span,
+            Node::Break(span) => span,
+            Node::Continue(span) => span,
         }
     }
 }
 
+// Prints the tree as an s-expression, e.g. `(let x (+ 1 (* 2 3)))`, so that its shape can be seen at a glance.
+impl Display for Node {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Node::Literal(Literal::Integer(value), _) => write!(f, "{}", value),
+            Node::Literal(Literal::Float(value), _) => write!(f, "{:?}", value),
+            Node::Literal(Literal::Boolean(value), _) => write!(f, "{}", value),
+            Node::Literal(Literal::String(value), _) => write!(f, "{:?}", value),
+
+            Node::BinaryOperation(operation, _) => write!(
+                f,
+                "({} {} {})",
+                operation.operator, operation.left, operation.right
+            ),
+
+            Node::UnaryOperation(operation, _) => {
+                write!(f, "({} {})", operation.operator, operation.operand)
+            }
+
+            Node::Group(expression, _) => write!(f, "(group {})", expression),
+
+            Node::LetOperation(operation, _) => {
+                let keyword = if operation.mutable { "var" } else { "let" };
+                match &operation.type_identifier {
+                    Some(type_identifier) => write!(
+                        f,
+                        "({} {}: {} {})",
+                        keyword, operation.name_identifier, type_identifier, operation.expression
+                    ),
+                    None => write!(
+                        f,
+                        "({} {} {})",
+                        keyword, operation.name_identifier, operation.expression
+                    ),
+                }
+            }
+
+            Node::AssignmentOperation(operation, _) => {
+                write!(f, "(= {} {})", operation.identifier, operation.expression)
+            }
+
+            Node::IndexAssignment(operation, _) => write!(
+                f,
+                "(= (index {} {}) {})",
+                operation.target, operation.index, operation.expression
+            ),
+
+            Node::Reference(identifier, _) => write!(f, "{}", identifier),
+            Node::Print(expression, _) => write!(f, "(print {})", expression),
+
+            Node::Interpolation(parts, _) => {
+                write!(f, "(interpolate")?;
+                for part in parts {
+                    match part {
+                        InterpolationPart::Text(text) => write!(f, " {:?}", text)?,
+                        InterpolationPart::Expression(expression) => write!(f, " {}", expression)?,
+                    }
+                }
+
+                write!(f, ")")
+            }
+
+            Node::Block(nodes, _) => {
+                write!(f, "(block")?;
+                for node in nodes {
+                    write!(f, " {}", node)?;
+                }
+
+                write!(f, ")")
+            }
+
+            Node::If(operation, _) => {
+                write!(f, "(if {} {}", operation.condition, operation.then_branch)?;
+                if let Some(else_branch) = &operation.else_branch {
+                    write!(f, " {}", else_branch)?;
+                }
+
+                write!(f, ")")
+            }
+
+            Node::While(operation, _) => {
+                write!(f, "(while {} {})", operation.condition, operation.body)
+            }
+
+            Node::For(operation, _) => write!(
+                f,
+                "(for {} {} {})",
+                operation.variable, operation.iterable, operation.body
+            ),
+
+            Node::Function(function, _) => {
+                write!(f, "(fn ")?;
+                if let Some(name) = &function.name {
+                    write!(f, "{} ", name)?;
+                }
+
+                write!(f, "(")?;
+                for (index, parameter) in function.parameters.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{}: {}", parameter.name, parameter.type_identifier)?;
+                }
+
+                write!(f, ")")?;
+                if let Some(return_type) = &function.return_type {
+                    write!(f, ": {}", return_type)?;
+                }
+
+                write!(f, " {})", function.body)
+            }
+
+            Node::Call(call, _) => {
+                write!(f, "(call {}", call.callee)?;
+                for argument in &call.arguments {
+                    write!(f, " {}", argument)?;
+                }
+
+                write!(f, ")")
+            }
+
+            Node::List(elements, _) => {
+                write!(f, "(list")?;
+                for element in elements {
+                    write!(f, " {}", element)?;
+                }
+
+                write!(f, ")")
+            }
+
+            Node::Index(operation, _) => {
+                write!(f, "(index {} {})", operation.target, operation.index)
+            }
+
+            Node::Return(Some(expression), _) => write!(f, "(return {})", expression),
+            Node::Return(None, _) => write!(f, "return"),
+
+            Node::Break(_) => write!(f, "break"),
+            Node::Continue(_) => write!(f, "continue"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum InterpolationPart {
+    Text(String),
+    Expression(Node),
+}
+
 #[derive(Debug, Clone)]
 pub enum VariableReference {
     Unresolved(String),
+
+    // Filled in by the typechecker once it knows the variable's type.
     Typed(String, Type),
 }
 
-#[derive(Debug, Clone)]
+impl VariableReference {
+    pub fn identifier(&self) -> &str {
+        match self {
+            VariableReference::Unresolved(identifier) => identifier,
+            VariableReference::Typed(identifier, _) => identifier,
+        }
+    }
+}
+
+impl Display for VariableReference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            VariableReference::Unresolved(identifier) => write!(f, "{}", identifier),
+            VariableReference::Typed(identifier, _) => write!(f, "{}", identifier),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum BinaryOperator {
     Plus,
     Minus,
     Multiply,
     Divide,
+    Modulo,
+
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+
+    And,
+    Or,
+
+    Range,
+    RangeInclusive,
+}
+
+impl BinaryOperator {
+    pub fn from_token(token: &Token) -> Option<BinaryOperator> {
+        let operator = match token {
+            Token::Plus(_) => BinaryOperator::Plus,
+            Token::Minus(_) => BinaryOperator::Minus,
+            Token::Asterisk(_) => BinaryOperator::Multiply,
+            Token::Slash(_) => BinaryOperator::Divide,
+            Token::Percent(_) => BinaryOperator::Modulo,
+
+            Token::EqualsEquals(_) => BinaryOperator::Equal,
+            Token::BangEquals(_) => BinaryOperator::NotEqual,
+            Token::Less(_) => BinaryOperator::Less,
+            Token::LessEquals(_) => BinaryOperator::LessEqual,
+            Token::Greater(_) => BinaryOperator::Greater,
+            Token::GreaterEquals(_) => BinaryOperator::GreaterEqual,
+
+            Token::Keyword(Keyword::And, _) => BinaryOperator::And,
+            Token::Keyword(Keyword::Or, _) => BinaryOperator::Or,
+
+            Token::DotDot(_) => BinaryOperator::Range,
+            Token::DotDotEquals(_) => BinaryOperator::RangeInclusive,
+
+            _ => return None,
+        };
+
+        Some(operator)
+    }
+
+    // The kind of operands that the operator accepts, for error messages.
+    pub fn operand_description(&self) -> &'static str {
+        match self {
+            BinaryOperator::Plus
+            | BinaryOperator::Less
+            | BinaryOperator::LessEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEqual => "numbers or strings",
+            BinaryOperator::Multiply => "numbers, or a string and an integer",
+            BinaryOperator::Minus | BinaryOperator::Divide | BinaryOperator::Modulo => "numbers",
+            BinaryOperator::Equal | BinaryOperator::NotEqual => {
+                "values of the same type, other than functions"
+            }
+            BinaryOperator::And | BinaryOperator::Or => "booleans",
+            BinaryOperator::Range | BinaryOperator::RangeInclusive => "integers",
+        }
+    }
+
+    // How tightly the operator binds, where operators with a higher precedence are grouped first.
+    // Assignment binds more loosely than all of these, and is handled separately since it is right associative.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Or => 1,
+            BinaryOperator::And => 2,
+            BinaryOperator::Equal | BinaryOperator::NotEqual => 3,
+            BinaryOperator::Less
+            | BinaryOperator::LessEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEqual => 4,
+            BinaryOperator::Range | BinaryOperator::RangeInclusive => 5,
+            BinaryOperator::Plus | BinaryOperator::Minus => 6,
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => 7,
+        }
+    }
+}
+
+impl Display for BinaryOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            BinaryOperator::Plus => "+",
+            BinaryOperator::Minus => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Modulo => "%",
+
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterEqual => ">=",
+
+            BinaryOperator::And => "and",
+            BinaryOperator::Or => "or",
+
+            BinaryOperator::Range => "..",
+            BinaryOperator::RangeInclusive => "..=",
+        };
+
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnaryOperator {
+    Negate,
+    Plus,
+    Not,
+}
+
+impl UnaryOperator {
+    pub fn from_token(token: &Token) -> Option<UnaryOperator> {
+        let operator = match token {
+            Token::Minus(_) => UnaryOperator::Negate,
+            Token::Plus(_) => UnaryOperator::Plus,
+            Token::Bang(_) | Token::Keyword(Keyword::Not, _) => UnaryOperator::Not,
+
+            _ => return None,
+        };
+
+        Some(operator)
+    }
+
+    // The kind of operand that the operator accepts, for error messages.
+    pub fn operand_description(&self) -> &'static str {
+        match self {
+            UnaryOperator::Negate | UnaryOperator::Plus => "numbers",
+            UnaryOperator::Not => "booleans",
+        }
+    }
+}
+
+impl Display for UnaryOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            UnaryOperator::Negate => "-",
+            UnaryOperator::Plus => "+",
+            UnaryOperator::Not => "not",
+        };
+
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UnaryOperationNode {
+    pub operator: UnaryOperator,
+    pub operand: Box<Node>,
 }
 
 #[derive(Debug, Clone)]
@@ -60,7 +452,11 @@ pub struct BinaryOperationNode {
 #[derive(Debug, Clone)]
 pub struct LetOperationNode {
     pub name_identifier: String,
-    pub type_identifier: Option<String>,
+
+    // Whether the variable was declared with `var` or `let mut`, so that it can be assigned to.
+    pub mutable: bool,
+
+    pub type_identifier: Option<TypeAnnotation>,
     pub expression: Box<Node>,
 }
 
@@ -69,3 +465,142 @@ pub struct AssignmentOperationNode {
     pub identifier: VariableReference,
     pub expression: Box<Node>,
 }
+
+#[derive(Debug, Clone)]
+pub struct IndexAssignmentNode {
+    pub target: Box<Node>,
+    pub index: Box<Node>,
+    pub expression: Box<Node>,
+
+    // Filled in by the typechecker once it knows the type of the list's elements.
+    pub element_type: Option<Type>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IfNode {
+    pub condition: Box<Node>,
+
+    // Always a block.
+    pub then_branch: Box<Node>,
+
+    // A block, or another if expression for `else if`.
+    pub else_branch: Option<Box<Node>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WhileNode {
+    pub condition: Box<Node>,
+
+    // Always a block.
+    pub body: Box<Node>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ForNode {
+    pub variable: String,
+    pub iterable: Box<Node>,
+
+    // Always a block.
+    pub body: Box<Node>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionNode {
+    // Anonymous functions don't have a name.
+    pub name: Option<String>,
+    pub parameters: Vec<Parameter>,
+
+    // Functions without a declared return type don't return a value.
+    pub return_type: Option<TypeAnnotation>,
+
+    // Always a block.
+    pub body: Box<Node>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    pub name: String,
+    pub type_identifier: TypeAnnotation,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct CallNode {
+    // Any expression that gives a function, which is usually its name.
+    pub callee: Box<Node>,
+    pub arguments: Vec<Node>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexNode {
+    // Any expression that gives a list.
+    pub target: Box<Node>,
+    pub index: Box<Node>,
+}
+
+// A type written in the source (e.g. `Integer`, `List<Integer>`, or `(Integer, Integer) -> Boolean` for a function).
+#[derive(Debug, Clone)]
+pub enum TypeAnnotation {
+    Named(String, Span),
+
+    // A type with type arguments, e.g. `List<Integer>`.
+    Generic {
+        name: String,
+        arguments: Vec<TypeAnnotation>,
+        span: Span,
+    },
+
+    Function {
+        parameters: Vec<TypeAnnotation>,
+        return_type: Box<TypeAnnotation>,
+        span: Span,
+    },
+}
+
+impl TypeAnnotation {
+    pub fn span(&self) -> &Span {
+        match self {
+            TypeAnnotation::Named(_, span) => span,
+            TypeAnnotation::Generic { span, .. } => span,
+            TypeAnnotation::Function { span, .. } => span,
+        }
+    }
+}
+
+impl Display for TypeAnnotation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeAnnotation::Named(name, _) => write!(f, "{}", name),
+            TypeAnnotation::Generic {
+                name, arguments, ..
+            } => {
+                write!(f, "{}<", name)?;
+                for (index, argument) in arguments.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{}", argument)?;
+                }
+
+                write!(f, ">")
+            }
+            TypeAnnotation::Function {
+                parameters,
+                return_type,
+                ..
+            } => {
+                write!(f, "(")?;
+                for (index, parameter) in parameters.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{}", parameter)?;
+                }
+
+                write!(f, ") -> {}", return_type)
+            }
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+use crate::diagnostic::Diagnostic;
+use crate::interpreter::context::Context;
+use crate::interpreter::value::Value;
+use crate::interpreter::Interpreter;
+use crate::location::FileId;
+use crate::parser::{Node, Parser, ParserError};
+use crate::source_map::SourceMap;
+use crate::tokenizer::{Tokenizer, TokenizerError};
+use crate::typechecker::Typechecker;
+use crate::{syntax_tree, typechecked_tree_with};
+
+// The name that diagnostics use for entries typed into the REPL.
+const NAME: &str = "<repl>";
+
+const HELP: &str = "\
+:type <expr>   Print the type of an expression
+:ast <expr>    Print the tree produced by the parser for an expression
+:load <file>   Run a script in the current context
+:reset         Forget every variable that has been defined
+:help          Print this message
+:quit          Exit the REPL";
+
+pub struct Repl {
+    context: Context,
+
+    // Knows about everything that earlier entries declared, so that later entries can use it.
+    typechecker: Typechecker,
+
+    // Every entry and loaded file is kept, so that diagnostics can point into earlier entries.
+    sources: SourceMap,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self {
+            context: Context::new("Root"),
+            typechecker: Typechecker::new(vec![]),
+            sources: SourceMap::new(),
+        }
+    }
+
+    // Reads entries from stdin until it is closed or `:quit` is entered.
+    pub fn run(&mut self) -> io::Result<()> {
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        let mut buffer = String::new();
+
+        println!("caoiscript REPL, type :help for a list of commands.");
+
+        loop {
+            print!("{}", if buffer.is_empty() { "> " } else { ". " });
+            io::stdout().flush()?;
+
+            let Some(line) = lines.next() else {
+                println!();
+                break;
+            };
+
+            let line = line?;
+
+            if buffer.is_empty() {
+                let trimmed = line.trim();
+                if trimmed.is_empty() {
+                    continue;
+                }
+
+                if let Some(command) = trimmed.strip_prefix(':') {
+                    if !self.run_meta_command(command) {
+                        break;
+                    }
+
+                    continue;
+                }
+            }
+
+            // An empty line while an entry is incomplete submits it anyway, so that the error is reported.
+            let force = !buffer.is_empty() && line.trim().is_empty();
+
+            buffer.push_str(&line);
+            buffer.push('\n');
+
+            if !force && Self::is_incomplete(&buffer) {
+                continue;
+            }
+
+            self.evaluate(&buffer);
+            buffer.clear();
+        }
+
+        Ok(())
+    }
+
+    // Returns false if the REPL should exit.
+    fn run_meta_command(&mut self, command: &str) -> bool {
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+
+        match name {
+            "type" => {
+                if let Some(tree) = self.parse(argument) {
+                    if let Some(mut node) = tree.into_iter().last() {
+                        // A copy is used, so that `:type` never declares anything.
+                        match self.typechecker.clone().typecheck_node(&mut node) {
+                            Ok(node_type) => println!("{}", node_type),
+                            Err(error) => Diagnostic::from(&error).emit(&self.sources),
+                        }
+                    }
+                }
+            }
+
+            "ast" => {
+                if let Some(tree) = self.parse(argument) {
+                    println!("{:#?}", tree);
+                }
+            }
+
+            "load" => match fs::read_to_string(argument) {
+                Ok(script) => {
+                    let file = self.sources.add(argument, script);
+                    if self.run_file(file).is_some() {
+                        println!("Loaded `{}`", argument);
+                    }
+                }
+                Err(error) => eprintln!("error: Unable to read `{}`: {}", argument, error),
+            },
+
+            "reset" => {
+                self.context = Context::new("Root");
+                self.typechecker = Typechecker::new(vec![]);
+                println!("Context has been reset");
+            }
+
+            "help" => println!("{}", HELP),
+            "quit" | "q" => return false,
+
+            _ => eprintln!(
+                "error: Unknown command `:{}`, type :help for a list of commands",
+                name
+            ),
+        }
+
+        true
+    }
+
+    fn evaluate(&mut self, source: &str) {
+        let file = self.sources.add(NAME, source);
+        match self.run_file(file) {
+            // An expression without a value (e.g. an if expression whose condition was false) prints nothing.
+            Some(Value::None) | None => {}
+            Some(value) => println!("{}", value),
+        }
+    }
+
+    // Typechecks and runs a file in the current context, reporting any errors. Returns `None` if there were errors,
+    // otherwise the value of its last expression (or `Value::None` if it ends with a statement).
+    fn run_file(&mut self, file: FileId) -> Option<Value> {
+        // The declarations are only kept if the whole file runs, since a runtime error can stop it before it reaches
+        // some of them.
+        let mut typechecker = self.typechecker.clone();
+        let tree = typechecked_tree_with(&mut typechecker, &self.sources, file).ok()?;
+
+        let is_expression = matches!(
+            tree.last(),
+            Some(node) if !matches!(
+                node,
+                Node::LetOperation(..)
+                    | Node::AssignmentOperation(..)
+                    | Node::IndexAssignment(..)
+                    | Node::Print(..)
+            )
+        );
+
+        // The typechecker's declarations are thrown away after a runtime error, so the variables are put back to match
+        // them, otherwise a variable could have a value of a different type than the typechecker expects.
+        let snapshot = self.context.snapshot();
+
+        match Interpreter::new(tree).run_in(&mut self.context) {
+            Ok(value) => {
+                self.typechecker = typechecker;
+                Some(if is_expression { value } else { Value::None })
+            }
+            Err(error) => {
+                self.context.restore(snapshot);
+                Diagnostic::from(&error).emit(&self.sources);
+                None
+            }
+        }
+    }
+
+    fn parse(&mut self, source: &str) -> Option<Vec<Node>> {
+        let file = self.sources.add(NAME, source);
+
+        let (tree, has_errors) = syntax_tree(&self.sources, file);
+        if has_errors {
+            return None;
+        }
+
+        Some(tree)
+    }
+
+    // An entry is incomplete if it ends inside a string, or if the parser ran out of tokens before it finished an
+    // expression.
+    fn is_incomplete(source: &str) -> bool {
+        let mut sources = SourceMap::new();
+        let file = sources.add(NAME, source);
+
+        let (tokens, tokenizer_errors) = Tokenizer::new(source.into(), file).process();
+        if tokenizer_errors
+            .iter()
+            .any(|error| matches!(error, TokenizerError::UnterminatedString(_)))
+        {
+            return true;
+        }
+
+        let (_, parser_errors) = Parser::new(tokens, sources.file(file)).parse();
+        parser_errors
+            .iter()
+            .any(|error| matches!(error, ParserError::UnexpectedEOF(_)))
+    }
+}
@@ -0,0 +1,99 @@
+use crate::location::{FileId, Location, Span};
+
+// How columns are counted when a byte offset is turned into a `Location`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ColumnUnit {
+    // Unicode scalar values, which is what a terminal displays.
+    #[default]
+    Characters,
+
+    // UTF-16 code units, which is what most editors (and the language server protocol) expect.
+    Utf16,
+}
+
+pub struct SourceFile {
+    pub name: String,
+    pub source: String,
+
+    // The byte offset that each line starts at.
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    fn new(name: String, source: String) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+
+        Self {
+            name,
+            source,
+            line_starts,
+        }
+    }
+
+    // The 0-based line that contains `offset`.
+    pub fn line_index(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        }
+    }
+
+    pub fn location(&self, offset: usize, unit: ColumnUnit) -> Location {
+        let line = self.line_index(offset);
+        let start = self.line_starts[line];
+        let prefix = &self.source[start..offset.clamp(start, self.source.len())];
+
+        let column = match unit {
+            ColumnUnit::Characters => prefix.chars().count(),
+            ColumnUnit::Utf16 => prefix.encode_utf16().count(),
+        };
+
+        Location { line, column }
+    }
+
+    // The text of a 0-based line, without its line ending.
+    pub fn line(&self, index: usize) -> &str {
+        let Some(&start) = self.line_starts.get(index) else {
+            return "";
+        };
+
+        let end = self
+            .line_starts
+            .get(index + 1)
+            .copied()
+            .unwrap_or(self.source.len());
+
+        self.source[start..end].trim_end_matches(['\n', '\r'])
+    }
+}
+
+// Every file that spans can point into, so that diagnostics can be rendered for any of them.
+#[derive(Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+
+    // The unit used when reporting positions to the user, e.g. in the header of a diagnostic.
+    pub column_unit: ColumnUnit,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, source: impl Into<String>) -> FileId {
+        self.files.push(SourceFile::new(name.into(), source.into()));
+        FileId(self.files.len() - 1)
+    }
+
+    pub fn file(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0]
+    }
+
+    // The location that a span starts at, counted in `column_unit`.
+    pub fn start_location(&self, span: &Span) -> Location {
+        self.file(span.file).location(span.start, self.column_unit)
+    }
+}
@@ -1,14 +1,12 @@
+#[derive(Clone)]
 pub struct ElementStream<T> {
     elements: Vec<T>,
-    pub index: usize
+    pub index: usize,
 }
 
 impl<T: Clone> ElementStream<T> {
     pub fn new(elements: Vec<T>) -> Self {
-        Self {
-            elements,
-            index: 0
-        }
+        Self { elements, index: 0 }
     }
 
     // Peeks in to the next optional element (current index).
@@ -16,6 +14,11 @@ impl<T: Clone> ElementStream<T> {
         self.elements.get(self.index).cloned()
     }
 
+    // Peeks at the element `offset` places after the next one, so `peek_at(0)` is the same as `peek()`.
+    pub fn peek_at(&self, offset: usize) -> Option<T> {
+        self.elements.get(self.index + offset).cloned()
+    }
+
     // Grabs the next optional element (current index), advances the counter, and returns that element.
     pub fn consume(&mut self) -> Option<T> {
         let element = self.peek();
@@ -1,8 +1,19 @@
-use std::process::exit;
+use std::any::Any;
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use requirements::TestRequirement;
 
+use crate::diagnostic::Diagnostic;
+use crate::interpreter::Interpreter;
+use crate::location::FileId;
 use crate::parser::Parser;
+use crate::source_map::SourceMap;
 use crate::tokenizer::Tokenizer;
 use crate::typechecker::Typechecker;
 
@@ -10,70 +21,427 @@ mod requirements;
 
 pub struct TestRunner {
     name: String,
-    script: String,
+    sources: SourceMap,
+    file: FileId,
     requirements: Vec<TestRequirement>,
+    warnings: Vec<Diagnostic>,
+}
+
+pub struct TestResult {
+    pub name: String,
+    pub failures: Vec<String>,
+    pub duration: Duration,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
 }
 
 impl TestRunner {
     pub fn new(name: String, script: String) -> Self {
+        let mut sources = SourceMap::new();
+        let file = sources.add(name.clone(), script);
+        let (requirements, warnings) = TestRequirement::parse(&sources.file(file).source, file);
+
         Self {
             name,
-            script: script.clone(),
-            requirements: TestRequirement::parse(script),
+            sources,
+            file,
+            requirements,
+            warnings,
+        }
+    }
+
+    // Runs every `.caoi` file under `directory`, printing a line for each test and a summary at the end.
+    // Returns whether every test passed.
+    pub fn run_directory(directory: &Path) -> io::Result<bool> {
+        let mut paths = vec![];
+        Self::find_scripts(directory, &mut paths)?;
+        paths.sort();
+
+        let started = Instant::now();
+        let mut failed = 0;
+
+        for path in &paths {
+            let name = path
+                .strip_prefix(directory)
+                .unwrap_or(path)
+                .display()
+                .to_string();
+
+            let script = fs::read_to_string(path)?;
+            let runner = TestRunner::new(name, script);
+            for warning in &runner.warnings {
+                warning.emit(&runner.sources);
+            }
+
+            let result = runner.run_catching_panics();
+
+            if result.passed() {
+                println!("✅ `{}` passed ({:.2?})", result.name, result.duration);
+            } else {
+                failed += 1;
+                println!("❌ `{}` failed ({:.2?})", result.name, result.duration);
+                for failure in &result.failures {
+                    println!("   {}", failure);
+                }
+            }
         }
+
+        println!(
+            "\n{} passed, {} failed, {} total in {:.2?}",
+            paths.len() - failed,
+            failed,
+            paths.len(),
+            started.elapsed()
+        );
+
+        Ok(failed == 0)
     }
 
-    pub fn run(&self) {
+    fn find_scripts(directory: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                Self::find_scripts(&path, paths)?;
+            } else if path
+                .extension()
+                .is_some_and(|extension| extension == "caoi")
+            {
+                paths.push(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn run(&self) -> TestResult {
+        let started = Instant::now();
+        let failures = self.failures();
+
+        TestResult {
+            name: self.name.clone(),
+            failures,
+            duration: started.elapsed(),
+        }
+    }
+
+    // Runs the test, turning a panic into a failure of this test, so that one crash doesn't stop the rest of the suite.
+    fn run_catching_panics(&self) -> TestResult {
+        let started = Instant::now();
+
+        panic::catch_unwind(AssertUnwindSafe(|| self.run())).unwrap_or_else(|payload| TestResult {
+            name: self.name.clone(),
+            failures: vec![format!(
+                "Panicked: {}",
+                Self::panic_message(payload.as_ref())
+            )],
+            duration: started.elapsed(),
+        })
+    }
+
+    // Panics usually carry a message as either a `&str` or a `String`.
+    fn panic_message(payload: &(dyn Any + Send)) -> String {
+        if let Some(message) = payload.downcast_ref::<&str>() {
+            return message.to_string();
+        }
+
+        match payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "unknown panic".into(),
+        }
+    }
+
+    fn failures(&self) -> Vec<String> {
         if self.requirements.is_empty() {
-            println!("❌ `{}` has no requirements!", self.name);
-            return;
+            return vec!["Test has no requirements".into()];
         }
 
-        println!("🏃‍♀️ Running test `{}`", self.name);
+        let mut failures = vec![];
+
+        let file = self.sources.file(self.file);
+        let mut tokenizer = Tokenizer::new(file.source.clone(), self.file);
+        let (tokens, tokenizer_errors) = tokenizer.process();
 
-        let mut tokenizer = Tokenizer::new(self.script.chars().collect());
-        let tokens = tokenizer.process();
+        let mut parser = Parser::new(tokens, file);
+        let (tree, parser_errors) = parser.parse();
 
-        println!("🎫 Tokens: {:#?}", tokens);
+        // Errors from the tokenizer count as parser errors, since both are syntax errors.
+        let diagnostics: Vec<Diagnostic> = tokenizer_errors
+            .iter()
+            .map(Diagnostic::from)
+            .chain(parser_errors.iter().map(Diagnostic::from))
+            .collect();
 
-        let mut parser = Parser::new(tokens);
-        let tree = parser.try_parse().unwrap();
+        let tree = if diagnostics.is_empty() {
+            Some(tree)
+        } else {
+            self.check_phase(&diagnostics, "parser", &mut failures);
+            None
+        };
+
+        for requirement in &self.requirements {
+            match requirement {
+                TestRequirement::ParserPass if tree.is_none() => {
+                    failures.push("Expected the parser to pass, but it failed".into())
+                }
+                TestRequirement::ParserFail if tree.is_some() => {
+                    failures.push("Expected the parser to fail, but it passed".into())
+                }
+                _ => {}
+            }
+        }
 
-        println!("🌳 AST: {:#?}", tree);
+        let Some(tree) = tree else {
+            return failures;
+        };
+
+        let printed_tree: Vec<String> = tree.iter().map(ToString::to_string).collect();
+        let expected_tree: Vec<&str> = self
+            .requirements
+            .iter()
+            .filter_map(|requirement| match requirement {
+                TestRequirement::Tree(tree) => Some(tree.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        if !expected_tree.is_empty() && printed_tree != expected_tree {
+            failures.push(format!(
+                "Expected the tree {:?}, but got {:?}",
+                expected_tree, printed_tree
+            ));
+        }
 
         let mut typechecker = Typechecker::new(tree);
-        let errors = typechecker.check();
+        let (tree, errors) = typechecker.check();
+        let diagnostics: Vec<Diagnostic> = errors.iter().map(Diagnostic::from).collect();
 
         for requirement in &self.requirements {
             match requirement {
                 TestRequirement::TypecheckerPass => {
-                    if errors.is_empty() {
-                        println!("✅ `{}` passed!", self.name);
-                    } else {
-                        println!("❌ `{}` failed!", self.name);
-                        for error in &errors {
-                            let line = error.location.line;
-                            let column = error.location.column;
-
-                            println!("====================");
-                            println!("Error at line {} column {}:", line + 1, column.saturating_sub(0));
-                            println!("{}", self.script.lines().nth(line).unwrap());
-                            println!("{}^", " ".repeat(column));
-                            println!("{}{}", " ".repeat(column), error.message);
-                            exit(-1);
-                        }
+                    for diagnostic in &diagnostics {
+                        failures.push(format!(
+                            "Expected the typechecker to pass, but it failed: {}",
+                            self.describe_diagnostic(diagnostic)
+                        ));
                     }
                 }
+                TestRequirement::TypecheckerFail if diagnostics.is_empty() => {
+                    failures.push("Expected the typechecker to fail, but it passed".into())
+                }
+                _ => {}
+            }
+        }
 
-                TestRequirement::TypecheckerFail => {
-                    if errors.is_empty() {
-                        println!("❌ `{}` failed!", self.name);
-                        exit(-1);
-                    } else {
-                        println!("✅ `{}` passed!", self.name);
-                    }
+        if !diagnostics.is_empty() {
+            self.check_phase(&diagnostics, "typechecker", &mut failures);
+            return failures;
+        }
+
+        // Scripts are only run when they have requirements on the result, so that a test can check how something is
+        // parsed or typechecked without being able to run it. Errors that are expected at this point are runtime
+        // errors.
+        let should_run = self.requirements.iter().any(|requirement| {
+            matches!(
+                requirement,
+                TestRequirement::Output(_)
+                    | TestRequirement::Value { .. }
+                    | TestRequirement::Error { .. }
+            )
+        });
+
+        if !should_run {
+            return failures;
+        }
+
+        let output = SharedBuffer::default();
+        let mut interpreter = Interpreter::with_output(tree, Box::new(output.clone()));
+        let (context, error) = interpreter.run();
+
+        let diagnostics: Vec<Diagnostic> = error.iter().map(Diagnostic::from).collect();
+        let expects_error = self
+            .requirements
+            .iter()
+            .any(|requirement| matches!(requirement, TestRequirement::Error { .. }));
+
+        if expects_error {
+            self.check_errors(&diagnostics, &mut failures);
+        } else {
+            for diagnostic in &diagnostics {
+                failures.push(format!(
+                    "Expected the script to run, but it failed: {}",
+                    self.describe_diagnostic(diagnostic)
+                ));
+            }
+        }
+
+        let printed = output.contents();
+        let printed: Vec<&str> = printed.lines().collect();
+        let expected: Vec<&str> = self
+            .requirements
+            .iter()
+            .filter_map(|requirement| match requirement {
+                TestRequirement::Output(line) => Some(line.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        if !expected.is_empty() && printed != expected {
+            failures.push(format!(
+                "Expected the output {:?}, but got {:?}",
+                expected, printed
+            ));
+        }
+
+        for requirement in &self.requirements {
+            let TestRequirement::Value { name, value } = requirement else {
+                continue;
+            };
+
+            match context.get_variable(name) {
+                Some(actual) if actual.to_string() == *value => {}
+                Some(actual) => failures.push(format!(
+                    "Expected `{}` to be {}, but it was {}",
+                    name, value, actual
+                )),
+                None => failures.push(format!("Expected `{}` to be defined", name)),
+            }
+        }
+
+        failures
+    }
+
+    // Called once a phase has reported errors, which stops the test before the next phase runs.
+    // Every `Error` requirement has to match one of the errors, and requirements on later phases can no longer pass.
+    fn check_phase(&self, diagnostics: &[Diagnostic], phase: &str, failures: &mut Vec<String>) {
+        let mut expected_tree = false;
+        let mut expected_to_run = false;
+
+        for requirement in &self.requirements {
+            match requirement {
+                TestRequirement::TypecheckerPass | TestRequirement::TypecheckerFail
+                    if phase == "parser" =>
+                {
+                    failures.push("Expected the typechecker to run, but the parser failed".into());
+                }
+
+                TestRequirement::Tree(_) if phase == "parser" => expected_tree = true,
+
+                TestRequirement::Output(_) | TestRequirement::Value { .. } => {
+                    expected_to_run = true
+                }
+
+                _ => {}
+            }
+        }
+
+        self.check_errors(diagnostics, failures);
+
+        if expected_tree {
+            failures.push("Expected a tree, but the parser failed".into());
+        }
+
+        if expected_to_run {
+            failures.push(format!(
+                "Expected the script to run, but the {} failed",
+                phase
+            ));
+        }
+    }
+
+    // Every `Error` requirement has to match one of the errors that were reported, and an `Errors` requirement has to
+    // match how many there were.
+    fn check_errors(&self, diagnostics: &[Diagnostic], failures: &mut Vec<String>) {
+        for requirement in &self.requirements {
+            if let TestRequirement::ErrorCount(count) = requirement {
+                if diagnostics.len() != *count {
+                    failures.push(format!(
+                        "Expected {} errors, but got {:?}",
+                        count,
+                        self.describe_diagnostics(diagnostics)
+                    ));
                 }
+
+                continue;
+            }
+
+            let TestRequirement::Error { message, position } = requirement else {
+                continue;
+            };
+
+            if diagnostics.is_empty() {
+                failures.push(format!(
+                    "Expected the error `{}`, but no errors were reported",
+                    Self::describe(message, position)
+                ));
+
+                continue;
+            }
+
+            let found = diagnostics.iter().any(|diagnostic| {
+                let actual_position = self.position(diagnostic);
+
+                diagnostic.message == *message
+                    && (position.is_none()
+                        || actual_position.is_none()
+                        || *position == actual_position)
+            });
+
+            if !found {
+                failures.push(format!(
+                    "Expected the error `{}`, but got {:?}",
+                    Self::describe(message, position),
+                    self.describe_diagnostics(diagnostics)
+                ));
             }
         }
     }
+
+    // The 1-based line and column that a diagnostic starts at.
+    fn position(&self, diagnostic: &Diagnostic) -> Option<(usize, usize)> {
+        let location = self.sources.start_location(diagnostic.span.as_ref()?);
+        Some((location.line + 1, location.column + 1))
+    }
+
+    fn describe_diagnostic(&self, diagnostic: &Diagnostic) -> String {
+        Self::describe(&diagnostic.message, &self.position(diagnostic))
+    }
+
+    fn describe_diagnostics(&self, diagnostics: &[Diagnostic]) -> Vec<String> {
+        diagnostics
+            .iter()
+            .map(|diagnostic| self.describe_diagnostic(diagnostic))
+            .collect()
+    }
+
+    fn describe(message: &str, position: &Option<(usize, usize)>) -> String {
+        match position {
+            Some((line, column)) => format!("{} at {}:{}", message, line, column),
+            None => message.into(),
+        }
+    }
+}
+
+// Collects everything the interpreter prints, so that it can be compared against `Output` requirements.
+#[derive(Default, Clone)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).into_owned()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
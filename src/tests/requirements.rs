@@ -1,58 +1,137 @@
-use std::str::SplitWhitespace;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::location::{FileId, Span};
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TestRequirement {
+    ParserPass,
+    ParserFail,
     TypecheckerPass,
-    TypecheckerFail
+    TypecheckerFail,
+
+    // An error with this message was reported, optionally at a 1-based line and column.
+    Error {
+        message: String,
+        position: Option<(usize, usize)>,
+    },
+
+    // The phase that failed reported exactly this many errors, so that a test can check that nothing extra was.
+    ErrorCount(usize),
+
+    // A statement parsed to this tree, written as an s-expression (e.g. `(- (- 10 2) 3)`). Tree requirements are
+    // matched in order against every statement in the script.
+    Tree(String),
+
+    // The script printed this line. Output requirements are matched in order.
+    Output(String),
+
+    // The variable had this value once the script finished.
+    Value {
+        name: String,
+        value: String,
+    },
 }
 
 impl TestRequirement {
-    pub fn parse(script: String) -> Vec<TestRequirement> {
+    // Parses every requirement in a script. Requirements that can't be understood are reported as warnings.
+    pub fn parse(script: &str, file: FileId) -> (Vec<TestRequirement>, Vec<Diagnostic>) {
         let mut requirements = vec![];
+        let mut warnings = vec![];
+        let mut offset = 0;
+
+        for line in script.split_inclusive('\n') {
+            let start = offset;
+            offset += line.len();
 
-        for line in script.lines() {
+            let line = line.trim_end_matches(['\n', '\r']);
             if !line.starts_with("##") {
                 continue;
             }
 
-            let line = line.trim_start_matches("##").trim();
-            let mut parts = line.split_whitespace();
+            let span = Span::new(file, start, start + line.len());
 
-            let keyword = match parts.next() {
-                Some(keyword) => keyword,
-                None => break,
+            let line = line.trim_start_matches("##").trim();
+            let (keyword, argument) = match line.split_once(char::is_whitespace) {
+                Some((keyword, argument)) => (keyword, argument.trim()),
+                None => (line, ""),
             };
 
+            if keyword.is_empty() {
+                break;
+            }
+
             let requirement = match keyword {
-                "Typechecker:" => Self::parse_typechecker_status(parts),
-                _ => {
-                    eprintln!("Unknown requirement `{}`", keyword);
-                    continue;
-                },
+                "Parser:" => Self::parse_status(argument, Self::ParserPass, Self::ParserFail),
+                "Typechecker:" => {
+                    Self::parse_status(argument, Self::TypecheckerPass, Self::TypecheckerFail)
+                }
+                "Error:" => Ok(Self::parse_error(argument)),
+                "Errors:" => Self::parse_error_count(argument),
+                "Tree:" => Ok(TestRequirement::Tree(argument.into())),
+                "Output:" => Ok(TestRequirement::Output(argument.into())),
+                "Value:" => Self::parse_value(argument),
+                _ => Err(format!("Unknown requirement `{}`", keyword)),
             };
 
-            if let Some(requirement) = requirement {
-                requirements.push(requirement);
+            match requirement {
+                Ok(requirement) => requirements.push(requirement),
+                Err(message) => {
+                    warnings.push(Diagnostic::new(Severity::Warning, message).with_span(span))
+                }
             }
         }
 
-        requirements
+        (requirements, warnings)
     }
 
-    fn parse_typechecker_status(mut parts: SplitWhitespace) -> Option<TestRequirement> {
-        let status = match parts.next(){
-            Some(next) => next,
-            None => return None,
-        };
-
+    fn parse_status(status: &str, pass: Self, fail: Self) -> Result<TestRequirement, String> {
         match status {
-            "Pass" => Some(TestRequirement::TypecheckerPass),
-            "Fail" => Some(TestRequirement::TypecheckerFail),
+            "Pass" => Ok(pass),
+            "Fail" => Ok(fail),
 
-            _ => {
-                eprintln!("Unknown typechecker status `{}`", status);
-                None
+            _ => Err(format!(
+                "Unknown status `{}`, expected `Pass` or `Fail`",
+                status
+            )),
+        }
+    }
+
+    // `<message> at <line>:<column>`, where the position is optional.
+    fn parse_error(argument: &str) -> TestRequirement {
+        let position = argument
+            .rsplit_once(" at ")
+            .and_then(|(message, position)| {
+                let (line, column) = position.split_once(':')?;
+                Some((message, (line.parse().ok()?, column.parse().ok()?)))
+            });
+
+        match position {
+            Some((message, position)) => TestRequirement::Error {
+                message: message.into(),
+                position: Some(position),
             },
+            None => TestRequirement::Error {
+                message: argument.into(),
+                position: None,
+            },
+        }
+    }
+
+    fn parse_error_count(argument: &str) -> Result<TestRequirement, String> {
+        match argument.parse() {
+            Ok(count) => Ok(TestRequirement::ErrorCount(count)),
+            Err(_) => Err(format!("Expected a number of errors, got `{}`", argument)),
         }
     }
-}
\ No newline at end of file
+
+    // `<name> = <value>`
+    fn parse_value(argument: &str) -> Result<TestRequirement, String> {
+        let Some((name, value)) = argument.split_once('=') else {
+            return Err(format!("Expected `<name> = <value>`, got `{}`", argument));
+        };
+
+        Ok(TestRequirement::Value {
+            name: name.trim().into(),
+            value: value.trim().into(),
+        })
+    }
+}
@@ -0,0 +1,70 @@
+use thiserror::Error;
+
+use crate::diagnostic::Diagnostic;
+use crate::location::Span;
+
+#[derive(Error, Debug, Clone)]
+pub enum TokenizerError {
+    #[error("Unknown character `{0}`")]
+    UnknownCharacter(char, Span),
+
+    #[error("Unterminated string")]
+    UnterminatedString(Span),
+
+    #[error("Unterminated block comment")]
+    UnterminatedComment(Span),
+
+    #[error("Invalid escape sequence `\\{0}`")]
+    InvalidEscape(char, Span),
+
+    #[error("Invalid unicode escape sequence")]
+    InvalidUnicodeEscape(Span),
+
+    #[error("Integer literal `{0}` is out of range")]
+    IntegerOutOfRange(String, Span),
+
+    #[error("Float literal `{0}` is out of range")]
+    FloatOutOfRange(String, Span),
+}
+
+impl TokenizerError {
+    pub fn span(&self) -> &Span {
+        match self {
+            TokenizerError::UnknownCharacter(_, span) => span,
+            TokenizerError::UnterminatedString(span) => span,
+            TokenizerError::UnterminatedComment(span) => span,
+            TokenizerError::InvalidEscape(_, span) => span,
+            TokenizerError::InvalidUnicodeEscape(span) => span,
+            TokenizerError::IntegerOutOfRange(_, span) => span,
+            TokenizerError::FloatOutOfRange(_, span) => span,
+        }
+    }
+}
+
+impl From<&TokenizerError> for Diagnostic {
+    fn from(value: &TokenizerError) -> Self {
+        let diagnostic = Diagnostic::error(value.to_string()).with_span(value.span().clone());
+
+        match value {
+            TokenizerError::UnterminatedString(_) => {
+                diagnostic.with_help("Add a `\"` to the end of the string")
+            }
+            TokenizerError::UnterminatedComment(_) => {
+                diagnostic.with_help("Add a `]#` to close the comment")
+            }
+            TokenizerError::InvalidEscape(..) => diagnostic.with_help(
+                "Valid escape sequences are `\\n`, `\\t`, `\\\"`, `\\\\`, `\\$` and `\\u{...}`",
+            ),
+            TokenizerError::InvalidUnicodeEscape(_) => diagnostic.with_help(
+                "Unicode escapes are written as `\\u{...}`, with 1 to 6 hexadecimal digits",
+            ),
+            TokenizerError::IntegerOutOfRange(..) => {
+                diagnostic.with_note(format!("The largest integer is {}", i64::MAX))
+            }
+            TokenizerError::FloatOutOfRange(..) => {
+                diagnostic.with_note(format!("The largest float is {:e}", f64::MAX))
+            }
+            TokenizerError::UnknownCharacter(..) => diagnostic,
+        }
+    }
+}
@@ -1,85 +1,135 @@
+pub use error::*;
 pub use token::*;
 
-use crate::location::Location;
+use crate::location::{FileId, Span};
 use crate::stream::ElementStream;
 
+pub mod error;
 pub mod token;
 
 pub struct Tokenizer {
     stream: ElementStream<char>,
-    new_lines: usize,
-    last_line_length: usize,
+    file: FileId,
+
+    // The byte offset of each character in the stream, followed by the length of the script.
+    offsets: Vec<usize>,
+
+    comments: Vec<Comment>,
+    errors: Vec<TokenizerError>,
 }
 
 impl Tokenizer {
-    pub fn new(script: String) -> Self {
-        let lines: Vec<&str> = script
-            .lines()
-            .filter(|line| !line.starts_with('#'))
-            .collect();
+    pub fn new(script: String, file: FileId) -> Self {
+        Self::with_base_offset(script, file, 0)
+    }
 
-        let characters = lines.join("\n").chars().collect();
+    // Creates a tokenizer for part of a file that starts at `base` (e.g. an interpolated expression in a string).
+    fn with_base_offset(script: String, file: FileId, base: usize) -> Self {
+        let (offsets, characters): (Vec<usize>, Vec<char>) = script.char_indices().unzip();
+        let offsets = offsets
+            .into_iter()
+            .chain([script.len()])
+            .map(|offset| base + offset)
+            .collect();
 
         Self {
             stream: ElementStream::new(characters),
-            new_lines: 0,
-            last_line_length: 0,
+            file,
+            offsets,
+            comments: vec![],
+            errors: vec![],
         }
     }
 
-    pub fn process(&mut self) -> Vec<Token> {
+    // Turns the script into tokens. Characters that can't be tokenized are reported as errors and skipped, so the
+    // tokens can still be parsed to find any other errors in the script.
+    pub fn process(&mut self) -> (Vec<Token>, Vec<TokenizerError>) {
         let mut tokens = vec![];
 
-        loop {
-            let Some(character) = self.stream.peek() else {
-                break;
-            };
+        while let Some(character) = self.stream.peek() {
+            let start = self.offset();
 
-            let location = Location {
-                line: self.new_lines,
-                column: self.stream.index - self.last_line_length,
-            };
+            // Single character tokens end right after the character.
+            let span = Span::new(self.file, start, start + character.len_utf8());
 
             let mut should_consume = true;
             let token = match character {
-                '+' => Some(Token::Plus(location)),
-                '-' => Some(Token::Minus(location)),
-                '*' => Some(Token::Asterisk(location)),
-                '/' => Some(Token::Slash(location)),
-                ':' => Some(Token::Colon(location)),
-                '=' => Some(Token::Equals(location)),
-
-                '"' => {
+                '+' => Some(Token::Plus(span)),
+                '-' if self.stream.peek_at(1) == Some('>') => {
                     self.stream.consume();
+                    Some(Token::Arrow(Span::new(self.file, start, start + 2)))
+                }
+
+                '-' => Some(Token::Minus(span)),
+                '*' => Some(Token::Asterisk(span)),
+                '/' => Some(Token::Slash(span)),
+                '%' => Some(Token::Percent(span)),
+                '(' => Some(Token::LeftParenthesis(span)),
+                ')' => Some(Token::RightParenthesis(span)),
+                '{' => Some(Token::LeftBrace(span)),
+                '}' => Some(Token::RightBrace(span)),
+                '[' => Some(Token::LeftBracket(span)),
+                ']' => Some(Token::RightBracket(span)),
+                ':' => Some(Token::Colon(span)),
+                ',' => Some(Token::Comma(span)),
+
+                '=' => {
                     should_consume = false;
+                    Some(self.parse_operator(start, Token::Equals, Token::EqualsEquals))
+                }
 
-                    let string_token = self.parse_string(location);
+                '<' => {
+                    should_consume = false;
+                    Some(self.parse_operator(start, Token::Less, Token::LessEquals))
+                }
+
+                '>' => {
+                    should_consume = false;
+                    Some(self.parse_operator(start, Token::Greater, Token::GreaterEquals))
+                }
+
+                '.' if self.stream.peek_at(1) == Some('.') => {
                     self.stream.consume();
+                    should_consume = false;
+                    Some(self.parse_operator(start, Token::DotDot, Token::DotDotEquals))
+                }
 
-                    Some(string_token)
+                '!' => {
+                    should_consume = false;
+                    Some(self.parse_operator(start, Token::Bang, Token::BangEquals))
                 }
 
-                '\n' => {
+                '"' => {
                     self.stream.consume();
-                    self.new_lines += 1;
-                    self.last_line_length = self.stream.index;
+                    should_consume = false;
+
+                    Some(self.parse_string(start))
+                }
 
+                '#' => {
+                    self.parse_comment(start);
                     continue;
                 }
 
                 _ => {
-                    if character.is_numeric() {
+                    if character.is_ascii_digit() {
                         should_consume = false;
-                        self.parse_integer(location)
+                        Some(self.parse_number(start))
                     } else if character.is_alphabetic() || character == '_' {
                         let identifier = self.read_string(|c| !c.is_alphabetic() && c != '_');
                         should_consume = false;
 
-                        match Self::parse_keyword(&identifier, &location) {
+                        let span = self.span_from(start);
+                        match Self::parse_keyword(&identifier, &span) {
                             Some(value) => Some(value),
-                            None => Some(Token::Identifier(identifier, location)),
+                            None => Some(Token::Identifier(identifier, span)),
                         }
                     } else {
+                        if !character.is_whitespace() {
+                            let error = TokenizerError::UnknownCharacter(character, span);
+                            self.errors.push(error);
+                        }
+
                         None
                     }
                 }
@@ -94,52 +144,333 @@ impl Tokenizer {
             }
         }
 
-        let location = Location {
-            line: self.new_lines,
-            column: self.stream.index,
-        };
+        tokens.push(Token::EndOfFile(self.span_from(self.offset())));
+        (tokens, std::mem::take(&mut self.errors))
+    }
 
-        tokens.push(Token::EndOfFile(location));
-        tokens
+    // The byte offset of the next character in the stream.
+    fn offset(&self) -> usize {
+        self.offsets[self.stream.index]
     }
 
-    fn parse_integer(&mut self, location: Location) -> Option<Token> {
-        let mut characters: Vec<char> = vec![];
+    // A span from `start` up to the next character in the stream.
+    fn span_from(&self, start: usize) -> Span {
+        Span::new(self.file, start, self.offset())
+    }
 
-        loop {
-            let Some(character) = self.stream.peek() else {
+    // The comments that were found by `process`, in the order they appear in the script.
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
+    fn parse_comment(&mut self, start: usize) {
+        // Skip the `#`.
+        self.stream.consume();
+
+        if self.stream.peek() != Some('[') {
+            let text = self.read_string(|c| c == '\n');
+            self.comments.push(Comment {
+                kind: CommentKind::Line,
+                text,
+                span: self.span_from(start),
+            });
+
+            return;
+        }
+
+        // Skip the `[`.
+        self.stream.consume();
+
+        let mut text = String::new();
+        let mut depth = 1;
+
+        while depth > 0 {
+            let Some(character) = self.stream.consume() else {
+                let span = Span::new(self.file, start, start + "#[".len());
+                self.errors.push(TokenizerError::UnterminatedComment(span));
                 break;
             };
 
-            if !character.is_numeric() {
-                break;
+            // Nested comments have to be closed before the outer comment is.
+            let next = self.stream.peek();
+            if character == '#' && next == Some('[') {
+                self.stream.consume();
+                depth += 1;
+                text.push_str("#[");
+            } else if character == ']' && next == Some('#') {
+                self.stream.consume();
+                depth -= 1;
+
+                if depth > 0 {
+                    text.push_str("]#");
+                }
+            } else {
+                text.push(character);
             }
+        }
+
+        self.comments.push(Comment {
+            kind: CommentKind::Block,
+            text,
+            span: self.span_from(start),
+        });
+    }
 
+    // An operator that has a different meaning when it is followed by `=` (e.g. `<` and `<=`).
+    fn parse_operator(
+        &mut self,
+        start: usize,
+        single: fn(Span) -> Token,
+        with_equals: fn(Span) -> Token,
+    ) -> Token {
+        self.stream.consume();
+
+        if self.stream.peek() == Some('=') {
             self.stream.consume();
-            characters.push(character);
+            return with_equals(self.span_from(start));
         }
 
-        let parsed_value = characters
-            .into_iter()
-            .map(|char| char.to_digit(10))
-            .try_fold(0, |ans, i| i.map(|i| ans * 10 + i));
+        single(self.span_from(start))
+    }
+
+    // An integer (e.g. `42`) or a float (e.g. `3.14`, `1e9` or `2.5e-3`).
+    fn parse_number(&mut self, start: usize) -> Token {
+        let mut digits = self.read_string(|c| !c.is_ascii_digit());
+        let mut is_float = false;
+
+        // A `.` is only part of the number if a digit follows it, so that `0..10` is still a range.
+        if self.stream.peek() == Some('.')
+            && self.stream.peek_at(1).is_some_and(|c| c.is_ascii_digit())
+        {
+            self.stream.consume();
+            digits.push('.');
+            digits.push_str(&self.read_string(|c| !c.is_ascii_digit()));
+            is_float = true;
+        }
+
+        if let Some(exponent @ ('e' | 'E')) = self.stream.peek() {
+            let sign = self.stream.peek_at(1).filter(|c| *c == '+' || *c == '-');
+            let digit_offset = if sign.is_some() { 2 } else { 1 };
+
+            if self
+                .stream
+                .peek_at(digit_offset)
+                .is_some_and(|c| c.is_ascii_digit())
+            {
+                self.stream.consume();
+                digits.push(exponent);
+
+                if let Some(sign) = sign {
+                    self.stream.consume();
+                    digits.push(sign);
+                }
+
+                digits.push_str(&self.read_string(|c| !c.is_ascii_digit()));
+                is_float = true;
+            }
+        }
+
+        let span = self.span_from(start);
+
+        // Out of range literals are still emitted so that the parser doesn't report a missing expression as well.
+        if is_float {
+            let value: f64 = digits.parse().unwrap_or(f64::INFINITY);
+            if value.is_infinite() {
+                self.errors
+                    .push(TokenizerError::FloatOutOfRange(digits, span.clone()));
+                return Token::Float(0.0, span);
+            }
+
+            return Token::Float(value, span);
+        }
 
-        parsed_value.map(|value| Token::Integer(value, location))
+        match digits.parse() {
+            Ok(value) if value <= i64::MIN.unsigned_abs() => Token::Integer(value, span),
+            _ => {
+                self.errors
+                    .push(TokenizerError::IntegerOutOfRange(digits, span.clone()));
+                Token::Integer(0, span)
+            }
+        }
     }
 
-    fn parse_string(&mut self, location: Location) -> Token {
-        let value = self.read_string(|c| c == '"');
-        Token::String(value, location)
+    // Reads the rest of a string, after its opening quote has been consumed.
+    fn parse_string(&mut self, start: usize) -> Token {
+        let mut value = String::new();
+        let mut parts = vec![];
+
+        // Where the line that the string started on ends, so that an unterminated string can be reported on that line.
+        let mut line_end = None;
+
+        let terminated = loop {
+            let Some(character) = self.stream.consume() else {
+                break false;
+            };
+
+            match character {
+                '"' => break true,
+                '\n' if line_end.is_none() => {
+                    line_end = Some(self.offsets[self.stream.index - 1]);
+                    value.push(character);
+                }
+                '\\' => {
+                    if let Some(escaped) = self.parse_escape() {
+                        value.push(escaped);
+                    }
+                }
+                '$' if self.stream.peek() == Some('{') => {
+                    self.stream.consume();
+
+                    if !value.is_empty() {
+                        parts.push(StringPart::Text(std::mem::take(&mut value)));
+                    }
+
+                    match self.parse_interpolation() {
+                        Some(part) => parts.push(part),
+                        None => break false,
+                    }
+                }
+                _ => value.push(character),
+            }
+        };
+
+        if !terminated {
+            let end = line_end.unwrap_or(self.offset());
+            self.errors
+                .push(TokenizerError::UnterminatedString(Span::new(
+                    self.file, start, end,
+                )));
+        }
+
+        // The closing quote is part of the string's span.
+        let span = self.span_from(start);
+
+        if parts.is_empty() {
+            return Token::String(value, span);
+        }
+
+        if !value.is_empty() {
+            parts.push(StringPart::Text(value));
+        }
+
+        Token::InterpolatedString(parts, span)
     }
 
-    fn read_string(&mut self, end_predicate: fn(char) -> bool) -> String {
-        let mut characters: Vec<char> = vec![];
+    // Reads the expression in a `${...}`, after the `${` has been consumed, and tokenizes it.
+    // Returns `None` if the end of the script was reached before the closing `}`.
+    fn parse_interpolation(&mut self) -> Option<StringPart> {
+        let start = self.offset();
+        let mut source = String::new();
+        let mut depth = 0;
 
         loop {
-            let Some(character) = self.stream.peek() else {
-                break;
-            };
+            let character = self.stream.consume()?;
+
+            match character {
+                '{' => depth += 1,
+                '}' if depth == 0 => break,
+                '}' => depth -= 1,
+
+                // A `}` inside a nested string doesn't close the interpolation.
+                '"' => {
+                    source.push(character);
+
+                    loop {
+                        let character = self.stream.consume()?;
+                        source.push(character);
 
+                        match character {
+                            '"' => break,
+                            '\\' => source.push(self.stream.consume()?),
+                            _ => {}
+                        }
+                    }
+
+                    continue;
+                }
+
+                _ => {}
+            }
+
+            source.push(character);
+        }
+
+        let mut tokenizer = Tokenizer::with_base_offset(source, self.file, start);
+        let (tokens, errors) = tokenizer.process();
+
+        self.errors.extend(errors);
+        self.comments.append(&mut tokenizer.comments);
+
+        // The span covers the expression, without the `${` and `}`.
+        let span = Span::new(self.file, start, self.offsets[self.stream.index - 1]);
+        Some(StringPart::Expression(tokens, span))
+    }
+
+    // Reads the character(s) after a `\\` in a string.
+    fn parse_escape(&mut self) -> Option<char> {
+        // The backslash has already been consumed.
+        let start = self.offsets[self.stream.index - 1];
+        let character = self.stream.consume()?;
+
+        let escaped = match character {
+            'n' => '\n',
+            't' => '\t',
+            '"' => '"',
+            '\\' => '\\',
+            '$' => '$',
+            'u' => return self.parse_unicode_escape(start),
+            _ => {
+                let span = self.span_from(start);
+                self.errors
+                    .push(TokenizerError::InvalidEscape(character, span));
+                return None;
+            }
+        };
+
+        Some(escaped)
+    }
+
+    // `\\u{1F600}`, after the `u` has been consumed.
+    fn parse_unicode_escape(&mut self, start: usize) -> Option<char> {
+        let mut digits = String::new();
+        let mut closed = false;
+
+        if self.stream.peek() == Some('{') {
+            self.stream.consume();
+
+            while let Some(character) = self.stream.peek() {
+                if character == '}' {
+                    self.stream.consume();
+                    closed = true;
+                    break;
+                }
+
+                if !character.is_ascii_hexdigit() || digits.len() == 6 {
+                    break;
+                }
+
+                self.stream.consume();
+                digits.push(character);
+            }
+        }
+
+        let character = u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .filter(|_| closed);
+
+        if character.is_none() {
+            let span = self.span_from(start);
+            self.errors.push(TokenizerError::InvalidUnicodeEscape(span));
+        }
+
+        character
+    }
+
+    fn read_string(&mut self, end_predicate: fn(char) -> bool) -> String {
+        let mut characters: Vec<char> = vec![];
+
+        while let Some(character) = self.stream.peek() {
             if end_predicate(character) {
                 break;
             }
@@ -151,12 +482,32 @@ impl Tokenizer {
         characters.into_iter().collect()
     }
 
-    fn parse_keyword(identifier: &str, location: &Location) -> Option<Token> {
+    fn parse_keyword(identifier: &str, span: &Span) -> Option<Token> {
         let keyword = match identifier {
             "let" => Keyword::Let,
+            "print" => Keyword::Print,
+            "and" => Keyword::And,
+            "or" => Keyword::Or,
+            "not" => Keyword::Not,
+            "if" => Keyword::If,
+            "else" => Keyword::Else,
+            "while" => Keyword::While,
+            "break" => Keyword::Break,
+            "continue" => Keyword::Continue,
+            "fn" => Keyword::Fn,
+            "return" => Keyword::Return,
+            "var" => Keyword::Var,
+            "mut" => Keyword::Mut,
+            "for" => Keyword::For,
+            "in" => Keyword::In,
+
+            // Booleans are literals rather than keywords.
+            "true" => return Some(Token::Boolean(true, span.clone())),
+            "false" => return Some(Token::Boolean(false, span.clone())),
+
             _ => return None,
         };
 
-        Some(Token::Keyword(keyword, location.clone()))
+        Some(Token::Keyword(keyword, span.clone()))
     }
 }
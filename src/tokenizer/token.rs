@@ -1,59 +1,226 @@
-use std::{
-    fmt,
-    fmt::Display,
-    fmt::Formatter,
-};
+use std::{fmt, fmt::Display, fmt::Formatter};
 
-use crate::location::Location;
+use crate::location::Span;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Keyword {
     Let,
+    Print,
+    And,
+    Or,
+    Not,
+    If,
+    Else,
+    While,
+    Break,
+    Continue,
+    For,
+    In,
+    Fn,
+    Return,
+    Var,
+    Mut,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommentKind {
+    // `# ...` up to the end of the line.
+    Line,
+
+    // `#[ ... ]#`, which can span multiple lines and be nested.
+    Block,
+}
+
+// Comments aren't tokens, but they are kept (with their spans) for tools like formatters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub kind: CommentKind,
+
+    // The text between the delimiters.
+    pub text: String,
+    pub span: Span,
+}
+
+// A piece of a string that contains interpolated expressions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringPart {
+    Text(String),
+
+    // The tokens of a `${...}` expression (ending with an end of file token), and the span of the expression.
+    Expression(Vec<Token>, Span),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
-    Keyword(Keyword, Location),
+    Keyword(Keyword, Span),
 
-    Integer(u32, Location),
-    String(String, Location),
-    Identifier(String, Location),
+    // The magnitude of an integer literal, which can be one more than `i64::MAX` so that `-9223372036854775808` can
+    // be written. The parser checks that only a negated literal is that large.
+    Integer(u64, Span),
+    Float(f64, Span),
+    Boolean(bool, Span),
+    String(String, Span),
+    InterpolatedString(Vec<StringPart>, Span),
+    Identifier(String, Span),
 
-    Plus(Location),
-    Minus(Location),
-    Slash(Location),
-    Asterisk(Location),
+    Plus(Span),
+    Minus(Span),
+    Slash(Span),
+    Asterisk(Span),
+    Percent(Span),
+    Bang(Span),
 
-    Equals(Location),
-    Colon(Location),
+    LeftParenthesis(Span),
+    RightParenthesis(Span),
+    LeftBrace(Span),
+    RightBrace(Span),
+    LeftBracket(Span),
+    RightBracket(Span),
 
-    EndOfFile(Location),
+    Equals(Span),
+    EqualsEquals(Span),
+    BangEquals(Span),
+    Less(Span),
+    LessEquals(Span),
+    Greater(Span),
+    GreaterEquals(Span),
+    DotDot(Span),
+    DotDotEquals(Span),
+    Colon(Span),
+    Comma(Span),
+    Arrow(Span),
+
+    EndOfFile(Span),
 }
 
 impl Token {
-    pub fn location(self) -> Location {
+    pub fn span(self) -> Span {
         match self {
-            Token::Keyword(_, location) => location,
+            Token::Keyword(_, span) => span,
+
+            Token::Integer(_, span) => span,
+            Token::Float(_, span) => span,
+            Token::Boolean(_, span) => span,
+            Token::String(_, span) => span,
+            Token::InterpolatedString(_, span) => span,
+            Token::Identifier(_, span) => span,
 
-            Token::Integer(_, location) => location,
-            Token::String(_, location) => location,
-            Token::Identifier(_, location) => location,
+            Token::Plus(span) => span,
+            Token::Minus(span) => span,
+            Token::Slash(span) => span,
+            Token::Asterisk(span) => span,
+            Token::Percent(span) => span,
+            Token::Bang(span) => span,
 
-            Token::Plus(location) => location,
-            Token::Minus(location) => location,
-            Token::Slash(location) => location,
-            Token::Asterisk(location) => location,
+            Token::LeftParenthesis(span) => span,
+            Token::RightParenthesis(span) => span,
+            Token::LeftBrace(span) => span,
+            Token::RightBrace(span) => span,
+            Token::LeftBracket(span) => span,
+            Token::RightBracket(span) => span,
 
-            Token::Equals(location) => location,
-            Token::Colon(location) => location,
+            Token::Equals(span) => span,
+            Token::EqualsEquals(span) => span,
+            Token::BangEquals(span) => span,
+            Token::Less(span) => span,
+            Token::LessEquals(span) => span,
+            Token::Greater(span) => span,
+            Token::GreaterEquals(span) => span,
+            Token::DotDot(span) => span,
+            Token::DotDotEquals(span) => span,
+            Token::Colon(span) => span,
+            Token::Comma(span) => span,
+            Token::Arrow(span) => span,
 
-            Token::EndOfFile(location) => location,
+            Token::EndOfFile(span) => span,
         }
     }
 }
 
+impl Keyword {
+    // Whether the keyword can only appear at the start of a statement, which is where the parser resumes after an
+    // error.
+    pub fn starts_statement(&self) -> bool {
+        matches!(
+            self,
+            Keyword::Let
+                | Keyword::Var
+                | Keyword::Print
+                | Keyword::If
+                | Keyword::While
+                | Keyword::For
+                | Keyword::Break
+                | Keyword::Continue
+                | Keyword::Fn
+                | Keyword::Return
+        )
+    }
+}
+
+impl Display for Keyword {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Keyword::Let => write!(f, "let"),
+            Keyword::Print => write!(f, "print"),
+            Keyword::And => write!(f, "and"),
+            Keyword::Or => write!(f, "or"),
+            Keyword::Not => write!(f, "not"),
+            Keyword::If => write!(f, "if"),
+            Keyword::Else => write!(f, "else"),
+            Keyword::While => write!(f, "while"),
+            Keyword::Break => write!(f, "break"),
+            Keyword::Continue => write!(f, "continue"),
+            Keyword::For => write!(f, "for"),
+            Keyword::In => write!(f, "in"),
+            Keyword::Fn => write!(f, "fn"),
+            Keyword::Return => write!(f, "return"),
+            Keyword::Var => write!(f, "var"),
+            Keyword::Mut => write!(f, "mut"),
+        }
+    }
+}
+
+// Describes the token in a way that can be used in an error message, e.g. "identifier `x`".
 impl Display for Token {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{:#?}", self)
+        match self {
+            Token::Keyword(keyword, _) => write!(f, "keyword `{}`", keyword),
+
+            Token::Integer(value, _) => write!(f, "integer `{}`", value),
+            Token::Float(value, _) => write!(f, "float `{:?}`", value),
+            Token::Boolean(value, _) => write!(f, "boolean `{}`", value),
+            Token::String(value, _) => write!(f, "string {:?}", value),
+            Token::InterpolatedString(..) => write!(f, "string"),
+            Token::Identifier(value, _) => write!(f, "identifier `{}`", value),
+
+            Token::Plus(_) => write!(f, "`+`"),
+            Token::Minus(_) => write!(f, "`-`"),
+            Token::Slash(_) => write!(f, "`/`"),
+            Token::Asterisk(_) => write!(f, "`*`"),
+            Token::Percent(_) => write!(f, "`%`"),
+            Token::Bang(_) => write!(f, "`!`"),
+
+            Token::LeftParenthesis(_) => write!(f, "`(`"),
+            Token::RightParenthesis(_) => write!(f, "`)`"),
+            Token::LeftBrace(_) => write!(f, "`{{`"),
+            Token::RightBrace(_) => write!(f, "`}}`"),
+            Token::LeftBracket(_) => write!(f, "`[`"),
+            Token::RightBracket(_) => write!(f, "`]`"),
+
+            Token::Equals(_) => write!(f, "`=`"),
+            Token::EqualsEquals(_) => write!(f, "`==`"),
+            Token::BangEquals(_) => write!(f, "`!=`"),
+            Token::Less(_) => write!(f, "`<`"),
+            Token::LessEquals(_) => write!(f, "`<=`"),
+            Token::Greater(_) => write!(f, "`>`"),
+            Token::GreaterEquals(_) => write!(f, "`>=`"),
+            Token::DotDot(_) => write!(f, "`..`"),
+            Token::DotDotEquals(_) => write!(f, "`..=`"),
+            Token::Colon(_) => write!(f, "`:`"),
+            Token::Comma(_) => write!(f, "`,`"),
+            Token::Arrow(_) => write!(f, "`->`"),
+
+            Token::EndOfFile(_) => write!(f, "end of file"),
+        }
     }
-}
\ No newline at end of file
+}
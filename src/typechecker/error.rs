@@ -1,31 +1,362 @@
-use crate::location::Location;
+use thiserror::Error;
+
+use crate::diagnostic::Diagnostic;
+use crate::location::Span;
+use crate::parser::{BinaryOperator, UnaryOperator};
+use crate::tokenizer::Keyword;
 use crate::typechecker::types::Type;
 
-#[derive(Debug, Clone)]
-pub struct TypecheckerError {
-    pub location: Location,
-    pub message: String,
+#[derive(Error, Debug, Clone)]
+pub enum TypecheckerError {
+    // The two sides of an operation have different types.
+    #[error("Mismatched types: {} and {}", left.0, right.0)]
+    MismatchedTypes {
+        left: (Type, Span),
+        right: (Type, Span),
+        span: Span,
+    },
+
+    // An expression doesn't have the type that was declared for it.
+    #[error("Mismatched types: {expected} and {found}")]
+    UnexpectedType {
+        expected: Type,
+        found: Type,
+        span: Span,
+    },
+
+    #[error("Invalid type: {0}")]
+    InvalidType(String, Span),
+
+    // A binary operator was applied to two operands of the same type, which it doesn't support.
+    #[error("Cannot apply `{operator}` to {} and {}", left.0, right.0)]
+    UnsupportedOperation {
+        operator: BinaryOperator,
+        left: (Type, Span),
+        right: (Type, Span),
+        span: Span,
+    },
+
+    // The condition of an if expression isn't a Boolean.
+    #[error("Expected a Boolean condition, found {0}")]
+    InvalidCondition(Type, Span),
+
+    // The branches of an if expression have different types.
+    #[error("`if` and `else` have different types: {} and {}", then_branch.0, else_branch.0)]
+    MismatchedBranches {
+        then_branch: (Type, Span),
+        else_branch: (Type, Span),
+        span: Span,
+    },
+
+    // A `break` or `continue` that isn't inside a loop.
+    #[error("`{0}` outside of a loop")]
+    OutsideLoop(Keyword, Span),
+
+    // A for loop over a type that doesn't have elements.
+    #[error("Cannot iterate over {0}")]
+    NotIterable(Type, Span),
+
+    // A reference or assignment to a variable that isn't defined in any scope around it.
+    #[error("Undefined variable `{0}`")]
+    UndefinedVariable(String, Span),
+
+    // A value assigned to a variable doesn't have the type the variable was defined with.
+    #[error("Cannot assign {found} to `{name}`, which is {expected}")]
+    MismatchedAssignment {
+        name: String,
+        expected: Type,
+        found: Type,
+        span: Span,
+    },
+
+    // An assignment to a variable that wasn't declared with `var` or `let mut`.
+    #[error("Cannot assign to immutable variable `{name}`")]
+    ImmutableAssignment {
+        name: String,
+        declaration: Span,
+        span: Span,
+    },
+
+    // A `let` or function declaration that would replace a variable that a function declared in the same scope uses.
+    #[error("Cannot redeclare `{name}`, since a function uses it")]
+    CapturedRedeclaration {
+        name: String,
+        capture: Span,
+        span: Span,
+    },
+
+    // A call to a function that hasn't been declared.
+    #[error("Undefined function `{0}`")]
+    UndefinedFunction(String, Span),
+
+    // A call to a value that isn't a function.
+    #[error("Cannot call {0}, since it isn't a function")]
+    NotCallable(Type, Span),
+
+    // A call with more or fewer arguments than the function has parameters. The function's name is only known when it
+    // is called by name.
+    #[error(
+        "Wrong number of arguments to {}: expected {expected}, found {found}",
+        describe_function(function, "the function")
+    )]
+    ArgumentCount {
+        function: Option<String>,
+        expected: usize,
+        found: usize,
+        span: Span,
+    },
+
+    // An argument doesn't have the type of its parameter. The parameter's span is where it was declared.
+    #[error("Expected {} for `{}`, found {found}", parameter.1, parameter.0)]
+    InvalidArgument {
+        parameter: (String, Type, Span),
+        found: Type,
+        span: Span,
+    },
+
+    // An argument doesn't have the type of its parameter, in a call to a function whose declaration isn't known (e.g.
+    // one that was passed in as an argument). The position counts from 1.
+    #[error("Expected {expected} for argument {position}, found {found}")]
+    MismatchedArgument {
+        position: usize,
+        expected: Type,
+        found: Type,
+        span: Span,
+    },
+
+    // A returned value doesn't have the function's declared return type.
+    #[error("Mismatched return type: expected {expected}, found {found}")]
+    MismatchedReturn {
+        expected: Type,
+        found: Type,
+        span: Span,
+    },
+
+    // The end of a function's body can be reached without returning a value. The error points at the signature and
+    // the closing `}` rather than the whole function, which can be long.
+    #[error(
+        "{} doesn't return {expected} on every path",
+        describe_function(function, "The function")
+    )]
+    MissingReturn {
+        function: Option<String>,
+        expected: Type,
+        span: Span,
+        end: Span,
+    },
+
+    // A `return` that isn't inside a function.
+    #[error("`return` outside of a function")]
+    OutsideFunction(Span),
+
+    // A list literal without any elements, where no list type is expected.
+    #[error("Cannot infer the type of an empty list")]
+    EmptyList(Span),
+
+    // The elements of a list literal don't all have the same type.
+    #[error("List elements have different types: {} and {}", first.0, element.0)]
+    MixedList {
+        first: (Type, Span),
+        element: (Type, Span),
+        span: Span,
+    },
+
+    // An index into a value that isn't a list.
+    #[error("Cannot index into {0}")]
+    NotIndexable(Type, Span),
+
+    // An index that isn't an Integer.
+    #[error("Expected an Integer index, found {0}")]
+    InvalidIndex(Type, Span),
+
+    // A value assigned to an element of a list doesn't have the list's element type.
+    #[error("Cannot assign {found} to an element of List<{expected}>")]
+    MismatchedElement {
+        expected: Type,
+        found: Type,
+        span: Span,
+    },
+
+    // A prefix operator was applied to a type that it doesn't support.
+    #[error("Cannot apply `{operator}` to {found}")]
+    InvalidOperand {
+        operator: UnaryOperator,
+        found: Type,
+        span: Span,
+    },
 }
 
 impl TypecheckerError {
-    pub fn mismatched_types(left: &Type, right: &Type, location: &Location) -> Self {
-        Self {
-            location: location.clone(),
-            message: format!("Mismatched types: {} and {}", left.to_string(), right.to_string()),
+    pub fn span(&self) -> &Span {
+        match self {
+            TypecheckerError::MismatchedTypes { span, .. } => span,
+            TypecheckerError::UnexpectedType { span, .. } => span,
+            TypecheckerError::InvalidType(_, span) => span,
+            TypecheckerError::UnsupportedOperation { span, .. } => span,
+            TypecheckerError::InvalidCondition(_, span) => span,
+            TypecheckerError::MismatchedBranches { span, .. } => span,
+            TypecheckerError::OutsideLoop(_, span) => span,
+            TypecheckerError::NotIterable(_, span) => span,
+            TypecheckerError::UndefinedVariable(_, span) => span,
+            TypecheckerError::MismatchedAssignment { span, .. } => span,
+            TypecheckerError::ImmutableAssignment { span, .. } => span,
+            TypecheckerError::CapturedRedeclaration { span, .. } => span,
+            TypecheckerError::UndefinedFunction(_, span) => span,
+            TypecheckerError::NotCallable(_, span) => span,
+            TypecheckerError::ArgumentCount { span, .. } => span,
+            TypecheckerError::InvalidArgument { span, .. } => span,
+            TypecheckerError::MismatchedArgument { span, .. } => span,
+            TypecheckerError::MismatchedReturn { span, .. } => span,
+            TypecheckerError::MissingReturn { span, .. } => span,
+            TypecheckerError::OutsideFunction(span) => span,
+            TypecheckerError::EmptyList(span) => span,
+            TypecheckerError::MixedList { span, .. } => span,
+            TypecheckerError::NotIndexable(_, span) => span,
+            TypecheckerError::InvalidIndex(_, span) => span,
+            TypecheckerError::MismatchedElement { span, .. } => span,
+            TypecheckerError::InvalidOperand { span, .. } => span,
         }
     }
+}
+
+impl From<&TypecheckerError> for Diagnostic {
+    fn from(value: &TypecheckerError) -> Self {
+        let diagnostic = Diagnostic::error(value.to_string()).with_span(value.span().clone());
+
+        match value {
+            TypecheckerError::MismatchedTypes { left, right, .. } => diagnostic
+                .with_label(left.1.clone(), left.0.to_string())
+                .with_label(right.1.clone(), right.0.to_string()),
 
-    pub fn invalid_type(type_identifier: &str, location: &Location) -> Self {
-        Self {
-            location: location.clone(),
-            message: format!("Invalid type: {}", type_identifier),
+            TypecheckerError::UnexpectedType {
+                expected, found, ..
+            } => diagnostic.with_note(format!(
+                "The declared type is {}, but the expression is {}",
+                expected, found
+            )),
+
+            TypecheckerError::InvalidType(..) => diagnostic.with_help(format!(
+                "Valid types are {}, lists such as `List<Integer>`, and function types such as `(Integer) -> Boolean`",
+                Type::NAMES.join(", ")
+            )),
+
+            TypecheckerError::UnsupportedOperation {
+                operator,
+                left,
+                right,
+                ..
+            } => diagnostic
+                .with_label(left.1.clone(), left.0.to_string())
+                .with_label(right.1.clone(), right.0.to_string())
+                .with_note(format!(
+                    "`{}` can only be applied to {}",
+                    operator,
+                    operator.operand_description()
+                )),
+
+            TypecheckerError::InvalidCondition(..) => diagnostic
+                .with_help("Compare the value to get a Boolean, e.g. `if x > 0 { ... }`"),
+
+            TypecheckerError::MismatchedBranches {
+                then_branch,
+                else_branch,
+                ..
+            } => diagnostic
+                .with_label(then_branch.1.clone(), then_branch.0.to_string())
+                .with_label(else_branch.1.clone(), else_branch.0.to_string())
+                .with_note("The value of an if expression comes from whichever branch runs, so both need the same type"),
+
+            TypecheckerError::OutsideLoop(keyword, _) => diagnostic.with_note(format!(
+                "`{}` can only be used inside the body of a `while` or `for` loop",
+                keyword
+            )),
+
+            TypecheckerError::NotIterable(..) => diagnostic.with_help(
+                "A for loop can iterate over a range or a list, e.g. `for i in 0..10 { ... }` or `for x in [1, 2] { ... }`",
+            ),
+
+            TypecheckerError::UndefinedVariable(name, _) => {
+                diagnostic.with_help(format!("Define it before using it, e.g. `let {} = ...`", name))
+            }
+
+            TypecheckerError::MismatchedAssignment { .. } => {
+                diagnostic.with_note("A variable keeps the type that it was defined with")
+            }
+
+            TypecheckerError::ImmutableAssignment {
+                name, declaration, ..
+            } => diagnostic
+                .with_label(declaration.clone(), format!("`{}` is declared here", name))
+                .with_help(format!(
+                    "Declare it with `var {}` (or `let mut {}`) so that it can be assigned to",
+                    name, name
+                )),
+
+            TypecheckerError::CapturedRedeclaration { name, capture, .. } => diagnostic
+                .with_label(capture.clone(), format!("`{}` is used by a function here", name))
+                .with_help(format!(
+                    "Use a different name, or declare `{}` with `var` and assign to it instead",
+                    name
+                )),
+
+            TypecheckerError::UndefinedFunction(..) => {
+                diagnostic.with_note("A function has to be declared before it is called")
+            }
+
+            TypecheckerError::NotCallable(..) => diagnostic,
+
+            TypecheckerError::ArgumentCount { .. } => diagnostic,
+
+            TypecheckerError::InvalidArgument { parameter, .. } => {
+                diagnostic.with_label(parameter.2.clone(), "parameter declared here")
+            }
+
+            TypecheckerError::MismatchedArgument { .. } => diagnostic,
+
+            TypecheckerError::MismatchedReturn { .. } => diagnostic,
+
+            TypecheckerError::MissingReturn { end, .. } => diagnostic
+                .with_label(end.clone(), "the end of the body can be reached without a `return`")
+                .with_help("End the body with a `return`, or with an expression of the return type"),
+
+            TypecheckerError::OutsideFunction(_) => diagnostic,
+
+            TypecheckerError::EmptyList(_) => {
+                diagnostic.with_help("Declare the type of the list, e.g. `let xs: List<Integer> = []`")
+            }
+
+            TypecheckerError::MixedList { first, element, .. } => diagnostic
+                .with_label(first.1.clone(), first.0.to_string())
+                .with_label(element.1.clone(), element.0.to_string())
+                .with_note("Every element of a list has to have the same type"),
+
+            TypecheckerError::NotIndexable(..) => diagnostic,
+
+            TypecheckerError::InvalidIndex(..) => diagnostic,
+
+            TypecheckerError::MismatchedElement { .. } => {
+                diagnostic.with_note("A list keeps the element type that it was created with")
+            }
+
+            TypecheckerError::InvalidOperand { operator, .. } => diagnostic.with_note(format!(
+                "`{}` can only be applied to {}",
+                operator,
+                operator.operand_description()
+            )),
         }
     }
 }
 
+// How errors refer to a function, which might not have a name (e.g. `fn(x: Integer): Integer { x }`).
+fn describe_function(name: &Option<String>, unnamed: &str) -> String {
+    match name {
+        Some(name) => format!("`{}`", name),
+        None => unnamed.into(),
+    }
+}
+
 impl<T> From<TypecheckerError> for Result<T, TypecheckerError> {
     fn from(value: TypecheckerError) -> Self {
         Err(value)
     }
 }
-
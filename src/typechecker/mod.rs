@@ -1,117 +1,996 @@
-use std::ops::Deref;
+use std::collections::HashMap;
+use std::mem;
+use std::ops::DerefMut;
 
 pub use error::*;
 pub use types::*;
 
-use crate::location::Location;
-use crate::parser::{BinaryOperationNode, LetOperationNode, Literal, Node};
+use crate::location::Span;
+use crate::parser::{
+    AssignmentOperationNode, BinaryOperationNode, BinaryOperator, CallNode, ForNode, FunctionNode,
+    IfNode, IndexAssignmentNode, IndexNode, InterpolationPart, LetOperationNode, Literal, Node,
+    TypeAnnotation, UnaryOperationNode, UnaryOperator, VariableReference, WhileNode,
+};
 use crate::stream::ElementStream;
+use crate::tokenizer::Keyword;
 
 pub mod error;
 pub mod types;
 
+#[derive(Clone)]
 pub struct Typechecker {
     stream: ElementStream<Node>,
+
+    // How many loops the node being checked is inside of, so that `break` and `continue` can be rejected outside them.
+    loop_depth: usize,
+
+    // The scopes around the node being checked, with the innermost scope last. A function's body is checked where
+    // it is declared, so these are the same scopes that the interpreter runs the body in.
+    scopes: Vec<Scope>,
+
+    // The declared return type of the function whose body is being checked, if any.
+    return_type: Option<Type>,
+
+    // The index of the scope that holds the parameters of the function whose body is being checked. The variables in
+    // the scopes before it are captured by the function when it uses them.
+    function_scope: usize,
 }
 
 impl Typechecker {
     pub fn new(nodes: Vec<Node>) -> Self {
         Self {
             stream: ElementStream::new(nodes),
+            loop_depth: 0,
+            scopes: vec![Scope::default()],
+            return_type: None,
+            function_scope: 0,
         }
     }
 
-    pub fn check(&mut self) -> Vec<TypecheckerError> {
+    // Checks every node in the stream, returning the nodes with the types that were resolved filled in (e.g. the type
+    // of the variable in an assignment), along with any errors.
+    pub fn check(&mut self) -> (Vec<Node>, Vec<TypecheckerError>) {
+        let mut nodes = vec![];
         let mut errors = vec![];
 
-        loop {
-            let Some(node) = self.stream.consume() else {
-                break;
-            };
-
-            let result = Self::typecheck_node(&node);
+        while let Some(mut node) = self.stream.consume() {
+            let result = self.typecheck_statement(&mut node);
             if let Err(value) = result {
                 errors.push(value)
             }
+
+            nodes.push(node);
         }
 
-        errors
+        (nodes, errors)
+    }
+
+    // Checks more nodes against everything that has been defined so far, e.g. the next entry in the REPL.
+    pub fn check_more(&mut self, nodes: Vec<Node>) -> (Vec<Node>, Vec<TypecheckerError>) {
+        self.stream = ElementStream::new(nodes);
+        self.check()
     }
 
-    pub fn typecheck_node(node: &Node) -> Result<Type, TypecheckerError> {
+    pub fn typecheck_node(&mut self, node: &mut Node) -> Result<Type, TypecheckerError> {
         match node {
-            Node::Literal(literal, _) => Self::typecheck_literal(literal),
+            Node::Literal(literal, _) => self.typecheck_literal(literal),
 
-            Node::BinaryOperation(operation, location) => {
-                Self::typecheck_binary_operation(operation, location)
+            Node::BinaryOperation(operation, span) => {
+                self.typecheck_binary_operation(operation, span)
             }
 
-            Node::LetOperation(operation, location) => {
-                Self::typecheck_let_operation(operation, location)
+            Node::UnaryOperation(operation, span) => {
+                self.typecheck_unary_operation(operation, span)
             }
 
-            Node::AssignmentOperation(operation, _) => {
-                Self::typecheck_node(operation.expression.deref())
+            Node::Group(expression, _) => self.typecheck_node(expression),
+
+            Node::LetOperation(operation, span) => self.typecheck_let_operation(operation, span),
+
+            Node::AssignmentOperation(operation, span) => {
+                self.typecheck_assignment_operation(operation, span)
             }
 
-            Node::Reference(_, _) => Ok(Type::None),
+            Node::IndexAssignment(operation, _) => self.typecheck_index_assignment(operation),
+
+            Node::Reference(identifier, span) => match self.variable(identifier, span) {
+                Some(variable) => Ok(variable.variable_type),
+                None => {
+                    TypecheckerError::UndefinedVariable(identifier.clone(), span.clone()).into()
+                }
+            },
+
+            Node::Print(expression, _) => {
+                self.typecheck_node(expression)?;
+                Ok(Type::None)
+            }
+
+            Node::Interpolation(parts, _) => self.typecheck_interpolation(parts),
+
+            Node::Block(nodes, _) => self.typecheck_block(nodes, true),
+
+            Node::If(operation, span) => self.typecheck_if(operation, span, true),
+
+            Node::While(operation, _) => self.typecheck_while(operation),
+
+            Node::For(operation, span) => self.typecheck_for(operation, span),
+
+            Node::Function(function, span) => self.typecheck_function(function, span),
+            Node::Call(call, span) => self.typecheck_call(call, span),
+            Node::List(elements, span) => self.typecheck_list(elements, span, None),
+            Node::Index(operation, _) => self.typecheck_index(operation),
+            Node::Return(expression, span) => {
+                self.typecheck_return(expression.as_deref_mut(), span)
+            }
+
+            Node::Break(span) => self.typecheck_loop_control(Keyword::Break, span),
+            Node::Continue(span) => self.typecheck_loop_control(Keyword::Continue, span),
+        }
+    }
+
+    // Typechecks a node whose value is discarded, e.g. a statement that isn't the last one in a block. The branches of
+    // an if expression only need the same type when its value is used.
+    pub fn typecheck_statement(&mut self, node: &mut Node) -> Result<Type, TypecheckerError> {
+        match node {
+            Node::Block(nodes, _) => self.typecheck_block(nodes, false),
+            Node::If(operation, span) => self.typecheck_if(operation, span, false),
+            _ => self.typecheck_node(node),
         }
     }
 
     // All literals are valid.
     // `<literal>`
-    pub fn typecheck_literal(literal: &Literal) -> Result<Type, TypecheckerError> {
+    pub fn typecheck_literal(&mut self, literal: &Literal) -> Result<Type, TypecheckerError> {
         Ok(match literal {
             Literal::Integer(_) => Type::Integer,
+            Literal::Float(_) => Type::Float,
+            Literal::Boolean(_) => Type::Boolean,
             Literal::String(_) => Type::String,
         })
     }
 
-    // Binary operations are only valid if the left and right operands are the same type.
+    // Any type can be interpolated into a string, but each expression still has to be valid.
+    // `"<text>${<expression>}"`
+    pub fn typecheck_interpolation(
+        &mut self,
+        parts: &mut [InterpolationPart],
+    ) -> Result<Type, TypecheckerError> {
+        for part in parts {
+            if let InterpolationPart::Expression(expression) = part {
+                self.typecheck_node(expression)?;
+            }
+        }
+
+        Ok(Type::String)
+    }
+
+    // Binary operations are only valid if the operator supports the types of both operands. Numbers can be mixed, and
+    // mixing an Integer with a Float gives a Float.
     // `<left> + <right>`
     pub fn typecheck_binary_operation(
-        operation: &BinaryOperationNode,
-        location: &Location,
+        &mut self,
+        operation: &mut BinaryOperationNode,
+        span: &Span,
+    ) -> Result<Type, TypecheckerError> {
+        let left_type = self.typecheck_node(operation.left.deref_mut())?;
+        let right_type = self.typecheck_node(operation.right.deref_mut())?;
+
+        if let Some(result_type) =
+            Self::binary_result_type(&operation.operator, &left_type, &right_type)
+        {
+            return Ok(result_type);
+        }
+
+        let comparable = left_type == right_type || left_type.numeric_result(&right_type).is_some();
+        let left = (left_type, operation.left.span().clone());
+        let right = (right_type, operation.right.span().clone());
+
+        if !comparable {
+            return TypecheckerError::MismatchedTypes {
+                left,
+                right,
+                span: span.clone(),
+            }
+            .into();
+        }
+
+        TypecheckerError::UnsupportedOperation {
+            operator: operation.operator.clone(),
+            left,
+            right,
+            span: span.clone(),
+        }
+        .into()
+    }
+
+    // The type of `<left> <operator> <right>`, or `None` if the operator can't be applied to those types.
+    fn binary_result_type(operator: &BinaryOperator, left: &Type, right: &Type) -> Option<Type> {
+        match operator {
+            BinaryOperator::Plus if *left == Type::String && *right == Type::String => {
+                Some(Type::String)
+            }
+
+            // A string can be repeated an integer number of times, from either side (e.g. `"ab" * 3`).
+            BinaryOperator::Multiply
+                if (*left == Type::String && *right == Type::Integer)
+                    || (*left == Type::Integer && *right == Type::String) =>
+            {
+                Some(Type::String)
+            }
+
+            BinaryOperator::Plus
+            | BinaryOperator::Minus
+            | BinaryOperator::Multiply
+            | BinaryOperator::Divide
+            | BinaryOperator::Modulo => left.numeric_result(right),
+
+            BinaryOperator::Equal | BinaryOperator::NotEqual => {
+                let comparable =
+                    (left == right && left.is_comparable()) || left.numeric_result(right).is_some();
+                comparable.then_some(Type::Boolean)
+            }
+
+            BinaryOperator::Less
+            | BinaryOperator::LessEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEqual => {
+                let strings = *left == Type::String && *right == Type::String;
+                let comparable = strings || left.numeric_result(right).is_some();
+                comparable.then_some(Type::Boolean)
+            }
+
+            BinaryOperator::And | BinaryOperator::Or => {
+                (*left == Type::Boolean && *right == Type::Boolean).then_some(Type::Boolean)
+            }
+
+            BinaryOperator::Range | BinaryOperator::RangeInclusive => {
+                (*left == Type::Integer && *right == Type::Integer).then_some(Type::Range)
+            }
+        }
+    }
+
+    // `-` and `+` are only valid on numbers, and `not` is only valid on booleans.
+    // `<operator> <operand>`
+    pub fn typecheck_unary_operation(
+        &mut self,
+        operation: &mut UnaryOperationNode,
+        span: &Span,
+    ) -> Result<Type, TypecheckerError> {
+        let operand_type = self.typecheck_node(operation.operand.deref_mut())?;
+
+        let valid = match operation.operator {
+            UnaryOperator::Negate | UnaryOperator::Plus => operand_type.is_numeric(),
+            UnaryOperator::Not => operand_type == Type::Boolean,
+        };
+
+        if !valid {
+            return TypecheckerError::InvalidOperand {
+                operator: operation.operator.clone(),
+                found: operand_type,
+                span: span.clone(),
+            }
+            .into();
+        }
+
+        Ok(operand_type)
+    }
+
+    // A block is valid if every statement in it is, and has the type of its last statement.
+    // `{ <statement>* }`
+    pub fn typecheck_block(
+        &mut self,
+        nodes: &mut [Node],
+        value_used: bool,
+    ) -> Result<Type, TypecheckerError> {
+        self.scopes.push(Scope::default());
+
+        let mut result = Ok(Type::None);
+        let last = nodes.len().saturating_sub(1);
+        for (index, node) in nodes.iter_mut().enumerate() {
+            // Only the last statement gives the block its value.
+            result = self.typecheck_branch(node, value_used && index == last);
+            if result.is_err() {
+                break;
+            }
+        }
+
+        self.scopes.pop();
+        result
+    }
+
+    // The condition has to be a Boolean. If the value is used, both branches need the same type since either could
+    // give the value, and without an else branch there might not be a value, so the type is None.
+    // `if <condition> { ... } else { ... }`
+    pub fn typecheck_if(
+        &mut self,
+        operation: &mut IfNode,
+        span: &Span,
+        value_used: bool,
+    ) -> Result<Type, TypecheckerError> {
+        self.typecheck_condition(operation.condition.deref_mut())?;
+
+        let then_type = self.typecheck_branch(operation.then_branch.deref_mut(), value_used)?;
+
+        let Some(else_branch) = &mut operation.else_branch else {
+            return Ok(Type::None);
+        };
+
+        let else_type = self.typecheck_branch(else_branch.deref_mut(), value_used)?;
+
+        if !value_used {
+            return Ok(Type::None);
+        }
+
+        if then_type == else_type {
+            return Ok(then_type);
+        }
+
+        // A branch that always returns from the function never gives the if expression a value.
+        if Self::always_returns(&operation.then_branch) {
+            return Ok(else_type);
+        }
+
+        if Self::always_returns(else_branch) {
+            return Ok(then_type);
+        }
+
+        TypecheckerError::MismatchedBranches {
+            then_branch: (then_type, Self::value_span(&operation.then_branch).clone()),
+            else_branch: (else_type, Self::value_span(else_branch).clone()),
+            span: span.clone(),
+        }
+        .into()
+    }
+
+    // The condition has to be a Boolean. A loop doesn't have a value, so the body's value is never used.
+    // `while <condition> { ... }`
+    pub fn typecheck_while(&mut self, operation: &mut WhileNode) -> Result<Type, TypecheckerError> {
+        self.typecheck_condition(operation.condition.deref_mut())?;
+
+        self.loop_depth += 1;
+        let result = self.typecheck_statement(operation.body.deref_mut());
+        self.loop_depth -= 1;
+
+        result?;
+        Ok(Type::None)
+    }
+
+    // The iterable has to be a range or collection, and the loop variable has the type of its elements. The variable is
+    // immutable, and only visible inside the body.
+    // `for <variable> in <iterable> { ... }`
+    pub fn typecheck_for(
+        &mut self,
+        operation: &mut ForNode,
+        span: &Span,
+    ) -> Result<Type, TypecheckerError> {
+        let iterable = operation.iterable.deref_mut();
+        let iterable_type = self.typecheck_node(iterable)?;
+
+        let Some(element_type) = iterable_type.element_type() else {
+            return TypecheckerError::NotIterable(iterable_type, iterable.span().clone()).into();
+        };
+
+        // The loop's header is where the variable is declared.
+        let declaration = span.to(iterable.span());
+
+        self.scopes.push(Scope::default());
+        self.define_variable(
+            &operation.variable,
+            Variable::immutable(element_type, declaration),
+        );
+        self.loop_depth += 1;
+        let result = self.typecheck_statement(operation.body.deref_mut());
+        self.loop_depth -= 1;
+        self.scopes.pop();
+
+        result?;
+        Ok(Type::None)
+    }
+
+    // `break` and `continue` are only valid inside a loop.
+    pub fn typecheck_loop_control(
+        &mut self,
+        keyword: Keyword,
+        span: &Span,
+    ) -> Result<Type, TypecheckerError> {
+        if self.loop_depth == 0 {
+            return TypecheckerError::OutsideLoop(keyword, span.clone()).into();
+        }
+
+        Ok(Type::None)
+    }
+
+    // The body is checked in a new scope that contains the parameters, nested inside the scope the function is declared
+    // in. It has to give the declared return type on every path through it, either with `return` or as its last
+    // expression. A declaration defines an immutable variable holding the function, while an anonymous function is an
+    // expression whose value is the function.
+    // `fn <name>(<parameter>: <type>, ...): <type> { ... }`
+    // `fn(<parameter>: <type>, ...): <type> { ... }`
+    pub fn typecheck_function(
+        &mut self,
+        function: &mut FunctionNode,
+        span: &Span,
+    ) -> Result<Type, TypecheckerError> {
+        let mut parameters = vec![];
+        for parameter in &function.parameters {
+            let parameter_type = Self::resolve_type(&parameter.type_identifier)?;
+            parameters.push((
+                parameter.name.clone(),
+                parameter_type,
+                parameter.span.clone(),
+            ));
+        }
+
+        let return_type = match &function.return_type {
+            Some(type_identifier) => Self::resolve_return_type(type_identifier)?,
+            None => Type::None,
+        };
+
+        // Parameters can't be assigned to.
+        let scope = Scope {
+            variables: parameters
+                .iter()
+                .map(|(name, parameter_type, span)| {
+                    let variable = Variable::immutable(parameter_type.clone(), span.clone());
+                    (name.clone(), variable)
+                })
+                .collect(),
+        };
+
+        let signature = Signature {
+            parameters,
+            return_type: return_type.clone(),
+        };
+
+        let function_type = signature.function_type();
+
+        // The function is declared before its body is checked, so that it can call itself.
+        if let Some(name) = &function.name {
+            self.check_redeclaration(name, span)?;
+            self.define_variable(name, Variable::function(signature, span.clone()));
+        }
+
+        // A loop around the declaration doesn't run the body, so `break` and `continue` can't reach it.
+        let outer_function_scope = mem::replace(&mut self.function_scope, self.scopes.len());
+        self.scopes.push(scope);
+        let outer_loop_depth = mem::replace(&mut self.loop_depth, 0);
+        let outer_return_type = self.return_type.replace(return_type.clone());
+
+        let result = self.typecheck_function_body(function, span, &return_type);
+
+        self.scopes.pop();
+        self.function_scope = outer_function_scope;
+        self.loop_depth = outer_loop_depth;
+        self.return_type = outer_return_type;
+
+        result?;
+        match function.name {
+            Some(_) => Ok(Type::None),
+            None => Ok(function_type),
+        }
+    }
+
+    // A function without a return type ignores the value of its body.
+    fn typecheck_function_body(
+        &mut self,
+        function: &mut FunctionNode,
+        span: &Span,
+        return_type: &Type,
+    ) -> Result<(), TypecheckerError> {
+        let body = function.body.deref_mut();
+
+        if *return_type == Type::None {
+            self.typecheck_statement(body)?;
+            return Ok(());
+        }
+
+        let body_type = self.typecheck_node(body)?;
+        if Self::always_returns(body) || return_type.accepts(&body_type) {
+            return Ok(());
+        }
+
+        if body_type == Type::None {
+            let signature = match &function.return_type {
+                Some(annotation) => span.to(annotation.span()),
+                None => span.clone(),
+            };
+
+            let body_span = body.span();
+            return TypecheckerError::MissingReturn {
+                function: function.name.clone(),
+                expected: return_type.clone(),
+                span: signature,
+                end: Span::new(body_span.file, body_span.end - 1, body_span.end),
+            }
+            .into();
+        }
+
+        TypecheckerError::MismatchedReturn {
+            expected: return_type.clone(),
+            found: body_type,
+            span: Self::value_span(body).clone(),
+        }
+        .into()
+    }
+
+    // The callee has to be a function, and each argument has to be the type of its parameter. A function that is called
+    // by name has to be declared (or stored in a variable) before the call.
+    // `<callee>(<argument>, ...)`
+    pub fn typecheck_call(
+        &mut self,
+        call: &mut CallNode,
+        span: &Span,
     ) -> Result<Type, TypecheckerError> {
-        let left_type = Self::typecheck_node(operation.left.deref())?;
-        let right_type = Self::typecheck_node(operation.right.deref())?;
+        let callee = call.callee.deref_mut();
+
+        // A declared function's signature knows its parameters' names, which make for better errors.
+        let (name, signature) = match callee {
+            Node::Reference(name, reference) => match self.variable(name, reference) {
+                Some(variable) => (Some(name.clone()), variable.signature),
+                None => {
+                    return TypecheckerError::UndefinedFunction(name.clone(), span.clone()).into()
+                }
+            },
+            _ => (None, None),
+        };
+
+        let callee_type = self.typecheck_node(callee)?;
+        let Type::Function(function) = callee_type else {
+            return TypecheckerError::NotCallable(callee_type, callee.span().clone()).into();
+        };
+
+        if call.arguments.len() != function.parameters.len() {
+            return TypecheckerError::ArgumentCount {
+                function: name,
+                expected: function.parameters.len(),
+                found: call.arguments.len(),
+                span: span.clone(),
+            }
+            .into();
+        }
+
+        for (index, (argument, parameter_type)) in call
+            .arguments
+            .iter_mut()
+            .zip(function.parameters)
+            .enumerate()
+        {
+            let argument_type = self.typecheck_expected(argument, &parameter_type)?;
+            if parameter_type.accepts(&argument_type) {
+                continue;
+            }
+
+            let span = argument.span().clone();
+            return match &signature {
+                Some(signature) => TypecheckerError::InvalidArgument {
+                    parameter: signature.parameters[index].clone(),
+                    found: argument_type,
+                    span,
+                },
+                None => TypecheckerError::MismatchedArgument {
+                    position: index + 1,
+                    expected: parameter_type,
+                    found: argument_type,
+                    span,
+                },
+            }
+            .into();
+        }
+
+        Ok(function.return_type)
+    }
+
+    // `return` is only valid inside a function, and its value has to be the declared return type. A function without
+    // a return type can only use `return` on its own.
+    // `return <expression>`
+    pub fn typecheck_return(
+        &mut self,
+        mut expression: Option<&mut Node>,
+        span: &Span,
+    ) -> Result<Type, TypecheckerError> {
+        let Some(return_type) = self.return_type.clone() else {
+            return TypecheckerError::OutsideFunction(span.clone()).into();
+        };
+
+        let found = match &mut expression {
+            Some(expression) => self.typecheck_expected(expression, &return_type)?,
+            None => Type::None,
+        };
+
+        if !return_type.accepts(&found) {
+            return TypecheckerError::MismatchedReturn {
+                expected: return_type,
+                found,
+                span: expression.as_deref().map_or(span, Node::span).clone(),
+            }
+            .into();
+        }
+
+        Ok(Type::None)
+    }
+
+    // Every element has to have the same type, which gives the list its type. An empty list doesn't have an element
+    // type of its own, so it can only be used where a list type is expected (e.g. `let xs: List<Integer> = []`). The
+    // expected element type is passed on to the elements, so that they can be empty lists as well (e.g. `[[]]`).
+    // `[<element>, ...]`
+    pub fn typecheck_list(
+        &mut self,
+        elements: &mut [Node],
+        span: &Span,
+        expected: Option<&Type>,
+    ) -> Result<Type, TypecheckerError> {
+        let Some((first, rest)) = elements.split_first_mut() else {
+            return match expected {
+                Some(element_type) => Ok(Type::List(Box::new(element_type.clone()))),
+                None => TypecheckerError::EmptyList(span.clone()).into(),
+            };
+        };
+
+        let element_type = match expected {
+            Some(expected) => self.typecheck_expected(first, expected)?,
+            None => self.typecheck_node(first)?,
+        };
+
+        for element in rest {
+            let found = self.typecheck_expected(element, &element_type)?;
+            if found != element_type {
+                return TypecheckerError::MixedList {
+                    first: (element_type, first.span().clone()),
+                    element: (found, element.span().clone()),
+                    span: span.clone(),
+                }
+                .into();
+            }
+        }
+
+        Ok(Type::List(Box::new(element_type)))
+    }
+
+    // Gives the list's element type. Whether the index is in bounds is only known once the script runs.
+    // `<list>[<index>]`
+    pub fn typecheck_index(&mut self, operation: &mut IndexNode) -> Result<Type, TypecheckerError> {
+        self.typecheck_element(operation.target.deref_mut(), operation.index.deref_mut())
+    }
+
+    // The value has to be the list's element type, or be widened to it, and the element type is filled in on the node
+    // so that the interpreter can widen the value as well. The list doesn't have to be in a mutable variable, since
+    // assigning to an element changes the list rather than the variable.
+    // `<list>[<index>] = <expression>`
+    pub fn typecheck_index_assignment(
+        &mut self,
+        operation: &mut IndexAssignmentNode,
+    ) -> Result<Type, TypecheckerError> {
+        let element_type =
+            self.typecheck_element(operation.target.deref_mut(), operation.index.deref_mut())?;
+
+        let expression = operation.expression.deref_mut();
+        let expression_type = self.typecheck_expected(expression, &element_type)?;
+
+        if !element_type.accepts(&expression_type) {
+            return TypecheckerError::MismatchedElement {
+                expected: element_type,
+                found: expression_type,
+                span: expression.span().clone(),
+            }
+            .into();
+        }
+
+        operation.element_type = Some(element_type.clone());
+        Ok(element_type)
+    }
+
+    // Only a list can be indexed, and only with an Integer.
+    fn typecheck_element(
+        &mut self,
+        target: &mut Node,
+        index: &mut Node,
+    ) -> Result<Type, TypecheckerError> {
+        let target_type = self.typecheck_node(target)?;
+        let Type::List(element_type) = target_type else {
+            return TypecheckerError::NotIndexable(target_type, target.span().clone()).into();
+        };
+
+        let index_type = self.typecheck_node(index)?;
+        if index_type != Type::Integer {
+            return TypecheckerError::InvalidIndex(index_type, index.span().clone()).into();
+        }
+
+        Ok(*element_type)
+    }
+
+    // Typechecks a value that is stored where `expected` is declared, e.g. an argument. An empty list takes the
+    // expected type, as long as that is a list, and so do empty lists nested inside a list.
+    fn typecheck_expected(
+        &mut self,
+        node: &mut Node,
+        expected: &Type,
+    ) -> Result<Type, TypecheckerError> {
+        match (node, expected) {
+            (Node::List(elements, span), Type::List(element_type)) => {
+                self.typecheck_list(elements, span, Some(element_type))
+            }
+            (node, _) => self.typecheck_node(node),
+        }
+    }
+
+    // Whether running the node always reaches a `return`, so that the end of it is never reached.
+    fn always_returns(node: &Node) -> bool {
+        match node {
+            Node::Return(..) => true,
+            Node::Block(nodes, _) => nodes.iter().any(Self::always_returns),
+            Node::If(operation, _) => operation.else_branch.as_ref().is_some_and(|else_branch| {
+                Self::always_returns(&operation.then_branch) && Self::always_returns(else_branch)
+            }),
+            _ => false,
+        }
+    }
+
+    fn typecheck_condition(&mut self, condition: &mut Node) -> Result<(), TypecheckerError> {
+        let condition_type = self.typecheck_node(condition)?;
+        if condition_type != Type::Boolean {
+            return TypecheckerError::InvalidCondition(condition_type, condition.span().clone())
+                .into();
+        }
 
-        if left_type != right_type {
-            return TypecheckerError::mismatched_types(&left_type, &right_type, location).into();
+        Ok(())
+    }
+
+    fn typecheck_branch(
+        &mut self,
+        node: &mut Node,
+        value_used: bool,
+    ) -> Result<Type, TypecheckerError> {
+        if value_used {
+            self.typecheck_node(node)
+        } else {
+            self.typecheck_statement(node)
         }
+    }
 
-        Ok(left_type)
+    // The span of the expression that gives a node its value, which is the last statement for a block.
+    fn value_span(node: &Node) -> &Span {
+        match node {
+            Node::Block(nodes, span) => nodes.last().map(Self::value_span).unwrap_or(span),
+            _ => node.span(),
+        }
     }
 
-    // Let operations are only valid if the expression is the same type as the declared type.
-    // The declared type is optional, so we need to check if it exists.
+    // Let operations are only valid if the expression is the same type as the declared type, or can be widened to it.
+    // The declared type is optional, so we need to check if it exists. The variable can only be assigned to later if
+    // it is declared with `var` (or `let mut`).
     // `let <name>: <type> = <expression>`
     pub fn typecheck_let_operation(
-        operation: &LetOperationNode,
-        location: &Location,
+        &mut self,
+        operation: &mut LetOperationNode,
+        span: &Span,
     ) -> Result<Type, TypecheckerError> {
-        let expression = operation.expression.deref();
-        let expression_type = Self::typecheck_node(expression)?;
+        self.check_redeclaration(&operation.name_identifier, span)?;
+
+        let expression = operation.expression.deref_mut();
 
         let Some(type_identifier) = &operation.type_identifier else {
+            let expression_type = self.typecheck_node(expression)?;
+            let variable = Variable::new(expression_type.clone(), operation.mutable, span.clone());
+            self.define_variable(&operation.name_identifier, variable);
             return Ok(expression_type);
         };
 
-        let declared_type = Type::from_string(type_identifier);
-        match declared_type {
-            Some(value) => {
-                if value != expression_type {
-                    return TypecheckerError::mismatched_types(
-                        &value,
-                        &expression_type,
-                        expression.location(),
-                    )
-                    .into();
-                }
+        let declared_type = Self::resolve_type(type_identifier)?;
+        let expression_type = self.typecheck_expected(expression, &declared_type);
+
+        // The variable is defined even if the expression is wrong, so that the rest of the script is still checked
+        // against the declared type instead of reporting the variable as undefined.
+        let variable = Variable::new(declared_type.clone(), operation.mutable, span.clone());
+        self.define_variable(&operation.name_identifier, variable);
 
-                Ok(value)
+        let expression_type = expression_type?;
+        if !declared_type.accepts(&expression_type) {
+            return TypecheckerError::UnexpectedType {
+                expected: declared_type,
+                found: expression_type,
+                span: expression.span().clone(),
             }
-            None => TypecheckerError::invalid_type(type_identifier, location).into(),
+            .into();
+        }
+
+        Ok(declared_type)
+    }
+
+    // Assignments are only valid if the variable is defined as mutable, and the value is the variable's type or can be
+    // widened to it. The variable's type is filled in on the node, so that the interpreter can widen the value as well.
+    // `<name> = <expression>`
+    pub fn typecheck_assignment_operation(
+        &mut self,
+        operation: &mut AssignmentOperationNode,
+        span: &Span,
+    ) -> Result<Type, TypecheckerError> {
+        let identifier = operation.identifier.identifier().to_string();
+        let expression = operation.expression.deref_mut();
+        let variable = self.variable(&identifier, span);
+
+        let expression_type = match &variable {
+            Some(variable) => self.typecheck_expected(expression, &variable.variable_type)?,
+            None => self.typecheck_node(expression)?,
+        };
+
+        let Some(Variable {
+            variable_type,
+            mutable,
+            declaration,
+            ..
+        }) = variable
+        else {
+            return TypecheckerError::UndefinedVariable(identifier, span.clone()).into();
+        };
+
+        if !mutable {
+            return TypecheckerError::ImmutableAssignment {
+                name: identifier,
+                declaration,
+                span: span.clone(),
+            }
+            .into();
+        }
+
+        if !variable_type.accepts(&expression_type) {
+            return TypecheckerError::MismatchedAssignment {
+                name: identifier,
+                expected: variable_type,
+                found: expression_type,
+                span: expression.span().clone(),
+            }
+            .into();
+        }
+
+        operation.identifier = VariableReference::Typed(identifier, variable_type.clone());
+        Ok(variable_type)
+    }
+
+    // The type that an annotation names, e.g. `Integer`, `List<Integer>` or `(Integer) -> Boolean`.
+    fn resolve_type(annotation: &TypeAnnotation) -> Result<Type, TypecheckerError> {
+        match annotation {
+            TypeAnnotation::Named(name, span) => match Type::from_string(name) {
+                Some(resolved_type) => Ok(resolved_type),
+                None => TypecheckerError::InvalidType(name.clone(), span.clone()).into(),
+            },
+
+            // `List` is the only type that takes a type argument.
+            TypeAnnotation::Generic {
+                name, arguments, ..
+            } if name == "List" && arguments.len() == 1 => {
+                let element_type = Self::resolve_type(&arguments[0])?;
+                Ok(Type::List(Box::new(element_type)))
+            }
+
+            TypeAnnotation::Generic { span, .. } => {
+                TypecheckerError::InvalidType(annotation.to_string(), span.clone()).into()
+            }
+
+            TypeAnnotation::Function {
+                parameters,
+                return_type,
+                ..
+            } => {
+                let parameters = parameters
+                    .iter()
+                    .map(Self::resolve_type)
+                    .collect::<Result<_, _>>()?;
+
+                let return_type = Self::resolve_return_type(return_type)?;
+                Ok(Type::Function(Box::new(FunctionType {
+                    parameters,
+                    return_type,
+                })))
+            }
+        }
+    }
+
+    // A function's return type can also be `None`, for a function type whose functions don't return a value (e.g.
+    // `(String) -> None`).
+    fn resolve_return_type(annotation: &TypeAnnotation) -> Result<Type, TypecheckerError> {
+        match annotation {
+            TypeAnnotation::Named(name, _) if name == "None" => Ok(Type::None),
+            _ => Self::resolve_type(annotation),
+        }
+    }
+
+    fn innermost_scope(&mut self) -> &mut Scope {
+        self.scopes
+            .last_mut()
+            .expect("There is always a root scope")
+    }
+
+    // Defines a variable in the innermost scope, replacing any variable with the same name in that scope and shadowing
+    // any in the scopes around it.
+    fn define_variable(&mut self, identifier: &str, variable: Variable) {
+        self.innermost_scope()
+            .variables
+            .insert(identifier.into(), variable);
+    }
+
+    // A function's body runs in the scope it was declared in, where a later `let` with the same name replaces the
+    // variable instead of shadowing it. The body was only checked against the old variable, so a variable that a
+    // function uses can't be declared again in the same scope.
+    fn check_redeclaration(&self, identifier: &str, span: &Span) -> Result<(), TypecheckerError> {
+        let scope = self.scopes.last().expect("There is always a root scope");
+        let Some(Variable {
+            captured: Some(capture),
+            ..
+        }) = scope.variables.get(identifier)
+        else {
+            return Ok(());
+        };
+
+        TypecheckerError::CapturedRedeclaration {
+            name: identifier.into(),
+            capture: capture.clone(),
+            span: span.clone(),
+        }
+        .into()
+    }
+
+    // Looks for a variable from the innermost scope outwards. If it is found outside the function being checked, it
+    // is marked as captured by the use at `span`.
+    fn variable(&mut self, identifier: &str, span: &Span) -> Option<Variable> {
+        let function_scope = self.function_scope;
+        let (index, scope) = self
+            .scopes
+            .iter_mut()
+            .enumerate()
+            .rev()
+            .find(|(_, scope)| scope.variables.contains_key(identifier))?;
+
+        let variable = scope.variables.get_mut(identifier)?;
+        if index < function_scope && variable.captured.is_none() {
+            variable.captured = Some(span.clone());
+        }
+
+        Some(variable.clone())
+    }
+}
+
+// The variables (including declared functions) defined by a block, a function body or the script itself, matching the
+// interpreter's scopes.
+#[derive(Clone, Default)]
+struct Scope {
+    variables: HashMap<String, Variable>,
+}
+
+#[derive(Clone)]
+struct Variable {
+    variable_type: Type,
+    mutable: bool,
+
+    // Where the variable was declared, so that errors about assigning to it can point there.
+    declaration: Span,
+
+    // The signature of a declared function, which is kept so that errors about its arguments can name its parameters.
+    signature: Option<Signature>,
+
+    // Where a function first uses the variable from outside its body, if one does.
+    captured: Option<Span>,
+}
+
+impl Variable {
+    fn new(variable_type: Type, mutable: bool, declaration: Span) -> Self {
+        Self {
+            variable_type,
+            mutable,
+            declaration,
+            signature: None,
+            captured: None,
+        }
+    }
+
+    fn immutable(variable_type: Type, declaration: Span) -> Self {
+        Self::new(variable_type, false, declaration)
+    }
+
+    // A declared function can't be assigned to, the same as a `let` binding.
+    fn function(signature: Signature, declaration: Span) -> Self {
+        Self {
+            signature: Some(signature.clone()),
+            ..Self::immutable(signature.function_type(), declaration)
         }
     }
 }
@@ -1,10 +1,22 @@
 use std::fmt;
 use std::fmt::Formatter;
 
+use crate::location::Span;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type {
     Integer,
+    Float,
+    Boolean,
     String,
+    Range,
+
+    // A list whose elements all have this type, e.g. `List<Integer>`.
+    List(Box<Type>),
+
+    // Boxed, since it is much larger than every other type.
+    Function(Box<FunctionType>),
+
     None,
 }
 
@@ -12,18 +24,115 @@ impl fmt::Display for Type {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Type::Integer => write!(f, "Integer"),
+            Type::Float => write!(f, "Float"),
+            Type::Boolean => write!(f, "Boolean"),
             Type::String => write!(f, "String"),
+            Type::Range => write!(f, "Range"),
+            Type::List(element_type) => write!(f, "List<{}>", element_type),
+            Type::Function(function) => write!(f, "{}", function),
             Type::None => write!(f, "None"),
         }
     }
 }
 
 impl Type {
+    // The names that can be used in a type annotation.
+    pub const NAMES: [&'static str; 5] = ["Integer", "Float", "Boolean", "String", "Range"];
+
     pub fn from_string(string: &str) -> Option<Self> {
         match string {
             "Integer" => Some(Type::Integer),
+            "Float" => Some(Type::Float),
+            "Boolean" => Some(Type::Boolean),
             "String" => Some(Type::String),
+            "Range" => Some(Type::Range),
             _ => None,
         }
     }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Type::Integer | Type::Float)
+    }
+
+    // The type of an arithmetic operation on two numbers. Integers are widened to floats when they are mixed, so
+    // `1 + 2.5` is a Float. Returns `None` if either type isn't a number.
+    pub fn numeric_result(&self, other: &Type) -> Option<Type> {
+        match (self, other) {
+            (Type::Integer, Type::Integer) => Some(Type::Integer),
+            (Type::Integer | Type::Float, Type::Integer | Type::Float) => Some(Type::Float),
+            _ => None,
+        }
+    }
+
+    // Whether a value of type `other` can be stored where this type was declared. This is the same type, or an
+    // Integer where a Float was declared (e.g. `let x: Float = 1`), but never the other way around since that could
+    // lose the fraction. Function types have to match exactly, since a function's result isn't widened when it is
+    // called through a variable.
+    pub fn accepts(&self, other: &Type) -> bool {
+        self == other || (*self == Type::Float && *other == Type::Integer)
+    }
+
+    // The type of the values that a for loop over this type visits, or `None` if it can't be iterated over.
+    pub fn element_type(&self) -> Option<Type> {
+        match self {
+            Type::Range => Some(Type::Integer),
+            Type::List(element_type) => Some(*element_type.clone()),
+            _ => None,
+        }
+    }
+
+    // Whether two values of this type can be compared with `==`. Functions can't, since there is no useful way to tell
+    // whether two of them are the same, and neither can lists of them.
+    pub fn is_comparable(&self) -> bool {
+        match self {
+            Type::Function(_) => false,
+            Type::List(element_type) => element_type.is_comparable(),
+            _ => true,
+        }
+    }
+}
+
+// The parameter types and return type of a function value (e.g. `(Integer, Integer) -> Boolean`). The return type is
+// None for a function that doesn't return a value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionType {
+    pub parameters: Vec<Type>,
+    pub return_type: Type,
+}
+
+impl fmt::Display for FunctionType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "(")?;
+        for (index, parameter) in self.parameters.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "{}", parameter)?;
+        }
+
+        write!(f, ") -> {}", self.return_type)
+    }
+}
+
+// The parameters and return type of a declared function. Each parameter keeps the span of its declaration, so that
+// errors about an argument can point at it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub parameters: Vec<(String, Type, Span)>,
+    pub return_type: Type,
+}
+
+impl Signature {
+    // The type of the function as a value, e.g. `(Integer, Integer) -> Integer`.
+    pub fn function_type(&self) -> Type {
+        Type::Function(Box::new(FunctionType {
+            parameters: self
+                .parameters
+                .iter()
+                .map(|parameter| parameter.1.clone())
+                .collect(),
+            return_type: self.return_type.clone(),
+        }))
+    }
 }